@@ -0,0 +1,274 @@
+//! Headless runner: drives the `circulation` module without opening a window
+//! and prints the final `Statistics`.
+//!
+//! ```text
+//! smart-road-sim [--seconds N | --cars N] [--max-seconds N] [--compare]
+//!                 [--scenario FILE] [--ambulance-every N] [--trips FILE]
+//!                 [--incidents FILE] [--trace FILE] [--config FILE]
+//!                 [--<key> VALUE]...
+//! ```
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use smart_road::circulation::{write_incidents, write_trips, ControllerKind, Scenario, State};
+use smart_road::config::{SimulationConfig, FPS};
+
+const USAGE: &str = "\
+Usage: smart-road-sim [OPTIONS]
+
+Options:
+  --seconds N      Simulate N seconds of traffic (default: 60)
+  --cars N         Spawn N cars, then run until the intersection is empty
+  --max-seconds N  Upper bound for --cars and --scenario runs (default: 3600)
+  --compare        Run every controller on the same traffic and print the
+                   statistics side by side
+  --scenario FILE  Spawn the cars listed in a TOML or JSON scenario instead
+                   of random traffic. Without --seconds or --cars, run until
+                   the last one has left
+  --ambulance-every N
+                   Also spawn an ambulance from a random direction every
+                   N seconds
+  --trips FILE     Write every car's trip to FILE, as JSON if it ends in
+                   .json and as CSV otherwise. With --compare, the name of
+                   the controller is appended to the file name
+  --incidents FILE Write every close call and collision to FILE, as JSON
+  --trace FILE     Record every tick to FILE as JSON lines, to watch the
+                   run again with `smart-road --replay FILE`
+  --config FILE    Load a TOML or JSON SimulationConfig
+  --<key> VALUE    Override a SimulationConfig field, e.g. --scan-distance 2.5,
+                   --seed 42, --controller reactive or --signals.all-red 2.
+                   --demand.enabled true replaces the fixed spawn interval
+                   with Poisson arrivals, see the [demand] section
+  -h, --help       Print this message";
+
+/// Condition to end the run on.
+enum Limit {
+    Seconds(u64),
+    Cars(usize),
+    /// Jusqu'à la sortie de la dernière voiture du scénario.
+    Scenario,
+}
+
+struct Args {
+    limit: Limit,
+    max_seconds: u64,
+    compare: bool,
+    /// Trafic scripté, à la place du trafic aléatoire.
+    scenario: Option<Scenario>,
+    /// Intervalle entre deux ambulances, en secondes.
+    ambulance_every: Option<u64>,
+    trips: Option<PathBuf>,
+    incidents: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    config: SimulationConfig,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let (config, rest) =
+        SimulationConfig::from_args(env::args().skip(1)).map_err(|e| e.to_string())?;
+    let mut limit = None;
+    let mut args = Args {
+        limit: Limit::Seconds(60),
+        max_seconds: 3600,
+        compare: false,
+        scenario: None,
+        ambulance_every: None,
+        trips: None,
+        incidents: None,
+        trace: None,
+        config,
+    };
+    let mut it = rest.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--seconds" => limit = Some(Limit::Seconds(parse_value(&arg, it.next())?)),
+            "--cars" => limit = Some(Limit::Cars(parse_value(&arg, it.next())?)),
+            "--max-seconds" => args.max_seconds = parse_value(&arg, it.next())?,
+            "--compare" => args.compare = true,
+            "--scenario" => {
+                let path: PathBuf = parse_value(&arg, it.next())?;
+                args.scenario = Some(Scenario::load(path).map_err(|e| e.to_string())?);
+            }
+            "--ambulance-every" => {
+                let seconds: u64 = parse_value(&arg, it.next())?;
+                if seconds == 0 {
+                    return Err(format!("invalid value `0` for `{arg}`"));
+                }
+                args.ambulance_every = Some(seconds);
+            }
+            "--trips" => args.trips = Some(parse_value(&arg, it.next())?),
+            "--incidents" => args.incidents = Some(parse_value(&arg, it.next())?),
+            "--trace" => args.trace = Some(parse_value(&arg, it.next())?),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument `{arg}`")),
+        }
+    }
+    args.limit = match (limit, &args.scenario) {
+        (Some(limit), _) => limit,
+        (None, Some(_)) => Limit::Scenario,
+        (None, None) => Limit::Seconds(60),
+    };
+    Ok(args)
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{flag}`"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for `{flag}`"))
+}
+
+fn cars_on_roads(state: &State) -> usize {
+    state
+        .roads
+        .iter()
+        .flat_map(|r| r.cars.iter())
+        .map(|cars| cars.len())
+        .sum()
+}
+
+/// Fait tourner une simulation jusqu'à la limite demandée.
+fn run(config: SimulationConfig, args: &Args) -> State {
+    let mut state = State::new(config);
+    if let Some(path) = &args.trace {
+        let path = output_path(path, &state, args.compare);
+        if let Err(err) = state.record_trace(&path) {
+            eprintln!("error: cannot write {}: {err}", path.display());
+            process::exit(1);
+        }
+    }
+    if let Some(scenario) = &args.scenario {
+        state.load_scenario(scenario);
+    }
+    // Même cadence que le mode aléatoire de l'interface, exprimée en ticks.
+    let spawn_interval = (state.config.random_interval * FPS / 1000).max(1);
+    let max_ticks = match args.limit {
+        Limit::Seconds(seconds) => seconds * FPS,
+        Limit::Cars(_) | Limit::Scenario => args.max_seconds * FPS,
+    };
+
+    while state.clock.tick() < max_ticks {
+        let spawning = match args.limit {
+            Limit::Seconds(_) => true,
+            Limit::Cars(n) => state.total_cars + state.queued() < n,
+            Limit::Scenario => !state.scenario_finished(),
+        };
+        if !spawning && cars_on_roads(&state) == 0 && state.queued() == 0 {
+            break;
+        }
+        let random = args.scenario.is_none();
+        if spawning && random && state.config.demand.enabled {
+            state.add_demand();
+        } else if spawning && random && state.clock.tick().is_multiple_of(spawn_interval) {
+            state.add_car_random();
+        }
+        if let Some(seconds) = args.ambulance_every {
+            let tick = state.clock.tick();
+            if spawning && tick > 0 && tick.is_multiple_of(seconds * FPS) {
+                if let Err(err) = state.add_emergency_random() {
+                    eprintln!("tick {tick}: cannot add an ambulance: {err}");
+                }
+            }
+        }
+        state.update();
+    }
+
+    state.stats.set_max_vehicles(state.total_cars);
+    state
+}
+
+/// Chemin du fichier à écrire pour `state`. Avec `--compare`, `trips.csv`
+/// devient `trips-reactive.csv`, `trips-reservation.csv`...
+fn output_path(path: &Path, state: &State, compare: bool) -> PathBuf {
+    if !compare {
+        return path.to_owned();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = format!("{stem}-{}", state.controller.name());
+    match path.extension() {
+        Some(ext) => path.with_file_name(name).with_extension(ext),
+        None => path.with_file_name(name),
+    }
+}
+
+/// Écrit les journaux de trajets et d'incidents de `state`, si demandés, et
+/// termine sa trace.
+fn export(state: &mut State, args: &Args) {
+    let mut written = Ok(());
+    if let Some(path) = &args.trace {
+        let path = output_path(path, state, args.compare);
+        written = state.finish_trace().map_err(|e| (path, e));
+    }
+    if let (Ok(()), Some(path)) = (&written, &args.trips) {
+        let path = output_path(path, state, args.compare);
+        written = write_trips(&path, state.stats.trips()).map_err(|e| (path, e));
+    }
+    if let (Ok(()), Some(path)) = (&written, &args.incidents) {
+        let path = output_path(path, state, args.compare);
+        written = write_incidents(&path, state.stats.incidents()).map_err(|e| (path, e));
+    }
+    if let Err((path, err)) = written {
+        eprintln!("error: cannot write {}: {err}", path.display());
+        process::exit(1);
+    }
+}
+
+/// Affiche les statistiques de plusieurs runs en colonnes.
+fn print_comparison(states: &[State]) {
+    let summaries: Vec<_> = states.iter().map(|s| s.stats.summary()).collect();
+    print!("{:<16}", "");
+    for state in states {
+        print!("{:>16}", state.controller.name());
+    }
+    println!();
+    for (row, (label, _)) in summaries[0].iter().enumerate() {
+        print!("{:<16}", label);
+        for summary in &summaries {
+            print!("{:>16}", summary[row].1);
+        }
+        println!();
+    }
+}
+
+fn main() {
+    let mut args = parse_args().unwrap_or_else(|err| {
+        eprintln!("error: {err}\n\n{USAGE}");
+        process::exit(2);
+    });
+
+    if !args.compare {
+        let mut state = run(args.config.clone(), &args);
+        println!("Seed: {}", state.seed);
+        println!(
+            "Simulated {:.1} s ({} ticks) with the {} controller",
+            state.clock.elapsed(),
+            state.clock.tick(),
+            state.controller.name()
+        );
+        println!("{}", state.stats);
+        export(&mut state, &args);
+        return;
+    }
+
+    // Tous les contrôleurs voient la même demande.
+    let seed = *args.config.seed.get_or_insert_with(rand::random);
+    println!("Seed: {seed}");
+    let mut states: Vec<State> = ControllerKind::ALL
+        .iter()
+        .map(|&controller| {
+            let config = SimulationConfig {
+                controller,
+                ..args.config.clone()
+            };
+            run(config, &args)
+        })
+        .collect();
+    print_comparison(&states);
+    for state in &mut states {
+        export(state, &args);
+    }
+}
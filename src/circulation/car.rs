@@ -1,27 +1,43 @@
-use macroquad::rand::gen_range;
-use std::time::SystemTime;
+use std::ops::RangeInclusive;
 
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::footprint::{Footprint, OrientedRect};
 use crate::circulation::path::{Path, Sector};
-use crate::circulation::{Direction, Statistics};
+use crate::circulation::profile::VehicleProfile;
+use crate::circulation::trip::{TripRecord, TripTracker};
+use crate::circulation::{Clock, Direction, Statistics};
 
-use crate::config::{
-    ACCELERATION_DISTANCE, CLOSE_CALL_DISTANCE, FPS, MAX_VELOCITY, SCAN_DISTANCE, SECTOR_WIDTH,
-    SPEED_LIMIT, WINDOW_SIZE,
-};
+use crate::config::SimulationConfig;
 
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Turning {
     Left,
     Straight,
     Right,
 }
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Moving {
     Up,
     Right,
     Down,
     Left,
 }
+
+impl Moving {
+    /// Vecteur unitaire dans le sens de la marche, en coordonnées de l'écran.
+    pub fn axis(&self) -> (f32, f32) {
+        match self {
+            Moving::Up => (0.0, -1.0),
+            Moving::Right => (1.0, 0.0),
+            Moving::Down => (0.0, 1.0),
+            Moving::Left => (-1.0, 0.0),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Borders {
     pub(crate) top: f32,
@@ -30,29 +46,113 @@ pub struct Borders {
     pub(crate) bottom: f32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Model {
     Standard,
     Sport,
     TaxiVert,
+    /// Véhicule d'urgence, prioritaire sur toutes les autres voitures. Jamais
+    /// tiré par `Model::random`.
+    Ambulance,
+    /// Camion-benne, assez long pour occuper deux secteurs de son `Path`.
+    GarbageTruck,
    // TaxiOrange,
      
 }
-// Ajuster les chances d'obtenir certaines voitures ici. Pourrait revoir et utiliser gen_range à la place.
 
-#[derive(Clone, Debug)]
+impl Model {
+    /// Modèle d'une voiture du trafic aléatoire. Le camion-benne en fait partie,
+    /// une voiture sur dix, pour que les véhicules longs soient aussi exercés
+    /// par le mode aléatoire; les véhicules d'urgence n'en font jamais partie.
+    // Ajuster les chances d'obtenir certaines voitures ici.
+    pub fn random<R: Rng>(rng: &mut R) -> Model {
+        match rng.gen_range(0..10) {
+            0 | 1 => Model::TaxiVert,
+            2 | 3 => Model::Sport,
+            4 => Model::GarbageTruck,
+            // 3 => Model::TaxiOrange,
+            _ => Model::Standard,
+        }
+    }
+
+    /// Dynamique et dimensions du modèle. Les dimensions, en largeurs de
+    /// secteur, sont mesurées sur les sprites.
+    pub fn profile(&self) -> VehicleProfile {
+        match self {
+            Model::Standard => VehicleProfile {
+                max_speed: 0.9,
+                max_acceleration: 4.0,
+                max_deceleration: 64.0,
+                footprint: Footprint { length: 0.75, width: 0.40 },
+                reaction_time: 0.1,
+            },
+            Model::Sport => VehicleProfile {
+                max_speed: 1.0,
+                max_acceleration: 7.0,
+                max_deceleration: 80.0,
+                footprint: Footprint { length: 0.72, width: 0.36 },
+                reaction_time: 0.05,
+            },
+            Model::TaxiVert => VehicleProfile {
+                max_speed: 0.85,
+                max_acceleration: 3.0,
+                max_deceleration: 56.0,
+                footprint: Footprint { length: 0.78, width: 0.42 },
+                reaction_time: 0.25,
+            },
+            Model::Ambulance => VehicleProfile {
+                max_speed: 1.0,
+                max_acceleration: 6.0,
+                max_deceleration: 56.0,
+                footprint: Footprint { length: 0.85, width: 0.42 },
+                reaction_time: 0.0,
+            },
+            Model::GarbageTruck => VehicleProfile {
+                max_speed: 0.7,
+                max_acceleration: 2.0,
+                max_deceleration: 16.0,
+                footprint: Footprint { length: 1.7, width: 0.5 },
+                reaction_time: 0.2,
+            },
+        }
+    }
+
+    /// `true` pour les véhicules d'urgence, voir `emergency`.
+    pub fn is_emergency(&self) -> bool {
+        matches!(self, Model::Ambulance)
+    }
+
+    /// Dimensions de la carrosserie.
+    pub fn footprint(&self) -> Footprint {
+        self.profile().footprint
+    }
+}
+
+/// Identifiant d'une voiture : son rang d'apparition, voir `State::total_cars`.
+pub type CarId = usize;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Car {
     pub x: f32,
     pub y: f32,
     pub index: usize,
     pub moving: Moving,
+    /// Vitesse, en multiples de `speed_limit` largeurs de secteur par seconde :
+    /// à `vel == 1.0`, la voiture parcourt `SimulationConfig::max_velocity`
+    /// pixels par tick.
     pub vel: f32,
     pub turning: Turning,
     pub path: Path,
     pub direction: Direction,
-    pub id: usize,
-    time: SystemTime,
+    pub id: CarId,
+    spawn_tick: u64,
+    sector_width: f32,
     pub model: Model,
+    profile: VehicleProfile,
+    /// Ticks à attendre avant de pouvoir accélérer de nouveau.
+    reaction: u32,
+    trip: TripTracker,
 }
 
 impl PartialEq for Car {
@@ -62,9 +162,17 @@ impl PartialEq for Car {
 }
 
 impl Car {
-    pub fn new(direction: Direction, turning: Turning, id: usize) -> Car {
+    pub fn new(
+        direction: Direction,
+        turning: Turning,
+        model: Model,
+        id: usize,
+        spawn_tick: u64,
+        config: &SimulationConfig,
+    ) -> Car {
         let path = Path::new(&direction, &turning);
-        let (x, y) = get_entry_coords(&path.sectors[0], &direction);
+        let sector_width = config.sector_width();
+        let (x, y) = get_entry_coords(&path.sectors[0], &direction, sector_width);
         Car {
             x,
             y,
@@ -80,26 +188,26 @@ impl Car {
             turning,
             path,
             direction,
-            time: SystemTime::now(),
-            model: match gen_range(0, 5) {
-                0 => Model::TaxiVert,
-                1 => Model::Sport,
-                // 3 => Model::TaxiOrange,
-                _ => Model::Standard,
-            },
+            spawn_tick,
+            sector_width,
+            profile: model.profile(),
+            model,
+            reaction: 0,
+            trip: TripTracker::default(),
         }
     }
 
     /// ### move_car
     /// Déplacer la voiture dans Path ainsi que dans Car.x et Car.y.
-    pub fn move_car(&mut self, all_cars: &[Car]) {
-        self.move_in_path(all_cars);
-        self.moving = self.sector(0).moving;
-        self.change_pos(all_cars);
+    pub fn move_car(&mut self, all_cars: &[Car], config: &SimulationConfig) {
+        self.advance(all_cars, config);
 
-        // La voiture tourne à droite, aucune logique supplémentaire nécessaire.
+        // Le virage à droite ne partage aucun secteur avec les autres voies : la
+        // voiture cède aux voitures qui coupent sa trajectoire et suit celle qui
+        // la précède.
         if self.turning == Turning::Right {
-            self.accelerate(SCAN_DISTANCE);
+            self.ray_casting(all_cars, config);
+            self.forward_scan(all_cars, config);
             return;
         }
 
@@ -109,20 +217,21 @@ impl Car {
         }
 
         if self.turning == Turning::Straight && (3..=7).contains(&self.index) {
-            self.sector_in_front(all_cars);
+            self.sector_in_front(all_cars, config);
         }
 
-        if self.index == 3 && self.sector_pos() > CLOSE_CALL_DISTANCE {
-            self.check_passing(all_cars);
+        // La voiture a presque traversé le premier secteur de l'intersection.
+        if self.index == 3 && self.sector_pos() > self.sector_width * 0.9 {
+            self.check_passing(all_cars, config);
         }
 
         if self.turning == Turning::Left && (5..=7).contains(&self.index) {
-            self.center_scan(all_cars);
+            self.center_scan(all_cars, config);
         }
 
         // La voiture qui va tout droit a atteint l'autre côté de l'intersection.
         if self.index >= 8 {
-            self.forward_scan(all_cars);
+            self.forward_scan(all_cars, config);
             return;
         }
 
@@ -130,83 +239,205 @@ impl Car {
         self.adjust_position();
 
         // Envoyer des rayons sur une certaine distance et vérifier la présence de voitures.
-        self.ray_casting(all_cars);
+        self.ray_casting(all_cars, config);
 
         // Scanner devant la voiture pour déterminer s'il est sûr d'accélérer ou s'il faut s'arrêter.
-        self.forward_scan(all_cars);
+        self.forward_scan(all_cars, config);
+    }
+
+    /// Fait avancer la voiture d'un tick à sa vitesse actuelle, sans capteurs :
+    /// seul un secteur occupé devant elle l'arrête.
+    pub fn advance(&mut self, all_cars: &[Car], config: &SimulationConfig) {
+        self.move_in_path(all_cars, config);
+        self.moving = self.sector(0).moving;
+        self.change_pos(all_cars, config);
     }
 
-    pub fn accelerate(&mut self, distance: f32) {
-        let x = if distance >= SCAN_DISTANCE {
+    /// Accélère vers la vitesse maximale du modèle, d'autant moins que `distance`
+    /// à la voiture de devant est courte. La voiture ne repart qu'après son
+    /// temps de réaction.
+    pub fn accelerate(&mut self, distance: f32, config: &SimulationConfig) {
+        if self.reaction > 0 {
+            self.reaction -= 1;
+            return;
+        }
+        let scan_distance = config.scan_distance_px();
+        let x = if distance >= scan_distance {
             1.0
         } else {
-            distance / SCAN_DISTANCE
+            distance / scan_distance
         };
-        let new_vel = (SPEED_LIMIT - self.vel) / FPS as f32 * x;
-        if self.vel < SPEED_LIMIT {
+        let top = self.top_speed(config);
+        let new_vel =
+            self.profile.acceleration_per_tick(config) * (top - self.vel) / top * x;
+        if self.vel < top {
             self.vel += new_vel;
         }
     }
 
-    pub fn brake(&mut self, distance: f32) {
-        let new_vel = self.vel - distance / SCAN_DISTANCE;
+    /// Ralentit vers une vitesse proportionnelle à `distance`, dans la limite de
+    /// la décélération du modèle.
+    pub fn brake(&mut self, distance: f32, config: &SimulationConfig) {
+        let new_vel = self.vel - distance / config.scan_distance_px();
         if new_vel < 0.0 {
             return;
         }
-        self.vel -= new_vel;
-        if self.vel < 0.3 {
+        let braking = self.profile.braking_per_tick(config);
+        self.vel -= new_vel.min(braking);
+        // L'arrêt final ne doit pas non plus dépasser la décélération du modèle.
+        if self.vel < braking.min(0.3) {
             self.stop();
         }
     }
 
+    /// Rapproche `vel` de `target` en un tick, dans les limites d'accélération et
+    /// de décélération du modèle. La voiture ne repart qu'après son temps de
+    /// réaction.
+    pub fn approach(&mut self, target: f32, config: &SimulationConfig) {
+        if self.vel < target {
+            if self.reaction > 0 {
+                self.reaction -= 1;
+                return;
+            }
+            self.vel = (self.vel + self.profile.acceleration_per_tick(config)).min(target);
+        } else {
+            self.vel = (self.vel - self.profile.braking_per_tick(config)).max(target);
+        }
+    }
+
     pub fn stop(&mut self) {
         self.vel = 0.0;
+        self.reaction = self.profile.reaction_ticks();
+    }
+
+    /// Freine pour s'arrêter avant d'entrer dans `path.sectors[index]`, sans
+    /// jamais accélérer ni dépasser la décélération du modèle. Retourne `true`
+    /// si la voiture est arrêtée à la ligne.
+    pub fn stop_before(&mut self, index: usize, config: &SimulationConfig) -> bool {
+        let distance = self.distance_to(index.saturating_sub(1));
+        let step = self.vel * config.max_velocity() * 1.05;
+        if distance <= step {
+            self.stop();
+            return true;
+        }
+
+        let scan_distance = config.scan_distance_px();
+        if distance < scan_distance {
+            // Vitesse d'approche proportionnelle à la distance restante, atteinte
+            // dans la limite de la décélération du modèle. Le plancher permet de
+            // s'arrêter à la ligne en un dernier tick de freinage.
+            let braking = self.profile.braking_per_tick(config);
+            let target =
+                (config.speed_limit * distance / scan_distance).max(braking.min(0.2));
+            if self.vel > target {
+                self.vel = (self.vel - braking).max(target);
+            }
+        }
+        false
     }
 
    
     /// Modifier la position de la voiture. Elle ira plus vite s'il n'y a pas de voitures autour et plus lentement s'il y a trop de voitures autour.
-    fn change_pos(&mut self, cars: &[Car]) {
+    fn change_pos(&mut self, cars: &[Car], config: &SimulationConfig) {
         let x = match cars
             .iter()
-            .filter(|c| self.id != c.id && self.calc_dist(c) < ACCELERATION_DISTANCE)
+            .filter(|c| {
+                self.id != c.id && self.calc_dist(c) < config.acceleration_distance_px()
+            })
             .count()
         {
             0 => 1.05,
             1 => 1.00,
             _ => 0.90,
         };
+        let step = (self.vel * x).min(self.top_speed(config)) * config.max_velocity();
         match self.moving {
-            Moving::Up => self.y -= self.vel * MAX_VELOCITY * x,
-            Moving::Right => self.x += self.vel * MAX_VELOCITY * x,
-            Moving::Down => self.y += self.vel * MAX_VELOCITY * x,
-            Moving::Left => self.x -= self.vel * MAX_VELOCITY * x,
+            Moving::Up => self.y -= step,
+            Moving::Right => self.x += step,
+            Moving::Down => self.y += step,
+            Moving::Left => self.x -= step,
         }
     }
 
     
     /// Obtenir la distance parcourue dans un Sector. Cela est utilisé pour résoudre les impasses.
     pub fn sector_pos(&self) -> f32 {
+        let width = self.sector_width;
         match self.moving {
-            Moving::Up => SECTOR_WIDTH - (self.y - self.sector(0).get_y() as f32 * SECTOR_WIDTH),
-            Moving::Right => SECTOR_WIDTH - (self.sector(0).get_x() as f32 * SECTOR_WIDTH - self.x),
-            Moving::Down => SECTOR_WIDTH - (self.sector(0).get_y() as f32 * SECTOR_WIDTH - self.y),
-            Moving::Left => SECTOR_WIDTH - (self.x - self.sector(0).get_x() as f32 * SECTOR_WIDTH),
+            Moving::Up => width - (self.y - self.sector(0).get_y() as f32 * width),
+            Moving::Right => width - (self.sector(0).get_x() as f32 * width - self.x),
+            Moving::Down => width - (self.sector(0).get_y() as f32 * width - self.y),
+            Moving::Left => width - (self.x - self.sector(0).get_x() as f32 * width),
         }
     }
 
 
+    /// Distance parcourue le long de `Path`, en pixels. Elle vaut `k * sector_width`
+    /// quand la voiture occupe entièrement `path.sectors[k]`.
+    pub fn progress(&self) -> f32 {
+        (self.index as f32 - 1.0) * self.sector_width + self.sector_pos()
+    }
+
+    /// Distance restante avant d'occuper entièrement `path.sectors[index]`, en pixels.
+    pub fn distance_to(&self, index: usize) -> f32 {
+        index as f32 * self.sector_width - self.progress()
+    }
+
+    /// `true` si la voiture occupe au moins un secteur de l'intersection.
+    pub fn in_intersection(&self) -> bool {
+        self.progress() > (self.path.entry_index() as f32 - 1.0) * self.sector_width
+            && !self.cleared_intersection()
+    }
+
+    /// `true` si l'arrière de la voiture a quitté le dernier secteur de
+    /// l'intersection.
+    pub fn cleared_intersection(&self) -> bool {
+        self.progress() >= (self.path.exit_index() + self.span()) as f32 * self.sector_width
+    }
+
+    /// Nombre de secteurs consécutifs de `Path` que la voiture occupe.
+    pub fn span(&self) -> usize {
+        self.profile.footprint.length.ceil() as usize
+    }
+
+    /// Secteurs occupés par la voiture, de l'arrière jusqu'à `sector(0)`.
+    pub fn occupied_sectors(&self) -> &[Sector] {
+        &self.path.sectors[self.occupied_indices()]
+    }
+
+    /// Indices dans `path.sectors` des secteurs occupés par la voiture.
+    pub fn occupied_indices(&self) -> RangeInclusive<usize> {
+        let head = self.index.min(self.path.sectors.len() - 1);
+        (head + 1).saturating_sub(self.span())..=head
+    }
+
+    pub fn occupies(&self, sector: &Sector) -> bool {
+        self.occupied_sectors().contains(sector)
+    }
+
     /// Déplace la voiture le long de son propre `Path` en incrémentant `path.currentss.
-/// S'arrête s'il y a une voiture dans le secteur devant.
-    fn move_in_path(&mut self, cars: &[Car]) {
+/// S'arrête si une voiture occupe le secteur devant.
+    fn move_in_path(&mut self, cars: &[Car], config: &SimulationConfig) {
         if self.index + 2 > self.path.sectors.len() {
             return;
         }
-        let car_ahead = cars.iter().any(|c| c.sector(0) == self.sector(1));
+        let next = self.sector(1);
+        let car_ahead = cars.iter().any(|c| {
+            c.id != self.id
+                && (c.occupies(&next)
+                    // Un véhicule long dépasse davantage de ses cases : on
+                    // attend que l'arrière de la voiture devant ait dégagé.
+                    || ((self.span() > 1 || c.span() > 1) && c.leaving(&next))
+                    // Deux voitures qui entrent dans le même secteur au même
+                    // tick : la plus ancienne passe.
+                    || (c.id < self.id && c.claims_next(config) && c.sector(1) == next))
+        });
 
         let next = &self.sector(0);
+        let step = self.vel * config.max_velocity();
         match self.moving {
             Moving::Up => {
-                if self.update_up(next) {
+                if self.update_up(next, step) {
                     if !car_ahead {
                         self.index += 1;
                     } else {
@@ -215,7 +446,7 @@ impl Car {
                 }
             }
             Moving::Right => {
-                if self.update_right(next) {
+                if self.update_right(next, step) {
                     if !car_ahead {
                         self.index += 1;
                     } else {
@@ -224,7 +455,7 @@ impl Car {
                 }
             }
             Moving::Down => {
-                if self.update_down(next) {
+                if self.update_down(next, step) {
                     if !car_ahead {
                         self.index += 1;
                     } else {
@@ -233,7 +464,7 @@ impl Car {
                 }
             }
             Moving::Left => {
-                if self.update_left(next) {
+                if self.update_left(next, step) {
                     if !car_ahead {
                         self.index += 1;
                     } else {
@@ -244,21 +475,37 @@ impl Car {
         }
     }
 
+    /// `true` si l'arrière de la voiture est encore dans `sector`, qu'elle
+    /// vient de quitter.
+    fn leaving(&self, sector: &Sector) -> bool {
+        let tail = *self.occupied_indices().start();
+        tail >= 1
+            && self.path.sectors[tail - 1] == *sector
+            && self.sector_pos() < self.sector_width / 2.0
+    }
+
+    /// `true` si la voiture entrera dans son prochain secteur pendant ce tick.
+    fn claims_next(&self, config: &SimulationConfig) -> bool {
+        self.index + 2 <= self.path.sectors.len()
+            && self.vel > 0.0
+            && self.sector_pos() + self.vel * config.max_velocity() >= self.sector_width
+    }
+
     // Fonctions auxiliaires `pour move_in_path`
-    fn update_up(&self, next: &Sector) -> bool {
-        self.y - self.vel * MAX_VELOCITY <= next.get_y() as f32 * SECTOR_WIDTH
+    fn update_up(&self, next: &Sector, step: f32) -> bool {
+        self.y - step <= next.get_y() as f32 * self.sector_width
     }
 
-    fn update_right(&self, next: &Sector) -> bool {
-        self.x + self.vel * MAX_VELOCITY >= next.get_x() as f32 * SECTOR_WIDTH
+    fn update_right(&self, next: &Sector, step: f32) -> bool {
+        self.x + step >= next.get_x() as f32 * self.sector_width
     }
 
-    fn update_down(&self, next: &Sector) -> bool {
-        self.y + self.vel * MAX_VELOCITY >= next.get_y() as f32 * SECTOR_WIDTH
+    fn update_down(&self, next: &Sector, step: f32) -> bool {
+        self.y + step >= next.get_y() as f32 * self.sector_width
     }
 
-    fn update_left(&self, next: &Sector) -> bool {
-        self.x - self.vel * MAX_VELOCITY <= next.get_x() as f32 * SECTOR_WIDTH
+    fn update_left(&self, next: &Sector, step: f32) -> bool {
+        self.x - step <= next.get_x() as f32 * self.sector_width
     }
 
     /// Mets à jour la direction de la voiture en fonction du secteur actuel dans `Path`.
@@ -269,11 +516,11 @@ impl Car {
         let sector = self.sector(0);
 
         if sector.get_x() != previous_sector.get_x() {
-            self.y = SECTOR_WIDTH * sector.get_y() as f32;
+            self.y = self.sector_width * sector.get_y() as f32;
         }
 
         if sector.get_y() != previous_sector.get_y() {
-            self.x = SECTOR_WIDTH * sector.get_x() as f32;
+            self.x = self.sector_width * sector.get_x() as f32;
         }
     }
 
@@ -283,52 +530,162 @@ impl Car {
         self.path.sectors[self.index + n].clone()
     }
 
-    
-    /// Get the borders of a `Car`.
+    pub fn profile(&self) -> &VehicleProfile {
+        &self.profile
+    }
+
+    /// Vitesse maximale de la voiture, dans l'unité de `vel`.
+    pub fn top_speed(&self, config: &SimulationConfig) -> f32 {
+        self.profile.top_speed(config)
+    }
+
+    /// Cases occupées par la voiture, de l'avant vers l'arrière : coin supérieur
+    /// gauche et sens de la marche. Les cases d'un véhicule long suivent son
+    /// `Path`, virages compris.
+    pub fn cells(&self) -> Vec<((f32, f32), Moving)> {
+        let width = self.sector_width;
+        let pos = self.sector_pos();
+        (0..self.span())
+            .map(|k| {
+                if k == 0 {
+                    return ((self.x, self.y), self.moving.clone());
+                }
+                match self.index.checked_sub(k) {
+                    // Même avancement que la case de tête, `k` secteurs plus tôt.
+                    Some(j) if j >= 1 => {
+                        let from = &self.path.sectors[j - 1];
+                        let moving = self.path.sectors[j].moving.clone();
+                        let (ax, ay) = moving.axis();
+                        let x = from.get_x() as f32 * width + ax * pos;
+                        let y = from.get_y() as f32 * width + ay * pos;
+                        ((x, y), moving)
+                    }
+                    // Avant le début de `Path`, hors de la fenêtre.
+                    _ => {
+                        let (ax, ay) = self.moving.axis();
+                        let back = k as f32 * width;
+                        ((self.x - ax * back, self.y - ay * back), self.moving.clone())
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Emprise de la voiture : un rectangle aux dimensions de son modèle par
+    /// case occupée, centré dans sa case et orienté selon son sens de marche.
+    pub fn rects(&self) -> Vec<OrientedRect> {
+        let footprint = self.profile.footprint;
+        let half = self.sector_width / 2.0;
+        let length = footprint.length / self.span() as f32 * self.sector_width;
+        self.cells()
+            .into_iter()
+            .map(|((x, y), moving)| {
+                OrientedRect::new(
+                    (x + half, y + half),
+                    moving.axis(),
+                    length,
+                    footprint.width * self.sector_width,
+                )
+            })
+            .collect()
+    }
+
+    /// Écart entre les emprises des deux voitures, en pixels; `0.0` si elles se
+    /// chevauchent.
+    pub fn gap(&self, other: &Car) -> f32 {
+        let others = other.rects();
+        self.rects()
+            .iter()
+            .flat_map(|a| others.iter().map(move |b| a.gap(b)))
+            .fold(f32::MAX, f32::min)
+    }
+
+    /// `true` si les emprises des deux voitures se chevauchent.
+    pub fn overlaps(&self, other: &Car) -> bool {
+        let others = other.rects();
+        self.rects()
+            .iter()
+            .any(|a| others.iter().any(|b| a.overlaps(b)))
+    }
+
+    /// Get the borders of the sector-sized cell the `Car` is drawn in.
     pub fn borders(&self) -> Borders {
         Borders {
             top: self.y,
-            right: self.x + SECTOR_WIDTH,
-            bottom: self.y + SECTOR_WIDTH,
+            right: self.x + self.sector_width,
+            bottom: self.y + self.sector_width,
             left: self.x,
         }
     }
 
-    pub fn add_time(&self, stats: &mut Statistics) {
+    /// Width of the sectors of the grid the car drives on, in pixels.
+    pub fn sector_width(&self) -> f32 {
+        self.sector_width
+    }
+
+    /// Tick de la simulation auquel la voiture a été créée.
+    pub fn spawn_tick(&self) -> u64 {
+        self.spawn_tick
+    }
+
+    pub fn add_time(&self, stats: &mut Statistics, clock: &Clock) {
+        stats.set_time(clock.since(self.spawn_tick));
+    }
+
+    /// Met à jour le trajet de la voiture à la fin du tick `tick`. `close` indique
+    /// qu'une autre voiture est à moins de `close_call_gap`.
+    pub fn record_trip(&mut self, tick: u64, close: bool, config: &SimulationConfig) {
+        let velocity = self.vel * self.sector_width;
+        let in_intersection = self.in_intersection();
+        // Temps perdu pendant ce tick par rapport à la vitesse maximale.
+        let lost = (1.0 - self.vel / self.top_speed(config)).max(0.0) * Clock::dt();
+        self.trip.record(tick, velocity, in_intersection, close, lost);
+    }
 
-        let duration = SystemTime::now().duration_since(self.time).unwrap();
-        stats.set_time(duration.as_secs_f32());
+    /// Trajet complet de la voiture, quand elle sort de la fenêtre.
+    pub fn trip_record(&self, clock: &Clock) -> TripRecord {
+        self.trip.finish(
+            self.id,
+            self.direction.clone(),
+            self.turning.clone(),
+            self.model.clone(),
+            self.spawn_tick,
+            clock.tick(),
+        )
     }
 
     /// ### is_done
     /// Checks if car has reached the end of their `Path`
-    pub fn is_done(&self) -> bool {
+    pub fn is_done(&self, config: &SimulationConfig) -> bool {
+        let window_size = config.window_size as f32;
+        // Longueur des cases qui suivent celle de `(x, y)`.
+        let tail = (self.span() - 1) as f32 * self.sector_width;
         match self.moving {
-            Moving::Up => self.borders().bottom <= 0.0,
-            Moving::Right => self.borders().left >= WINDOW_SIZE as f32,
-            Moving::Down => self.borders().top >= WINDOW_SIZE as f32,
-            Moving::Left => self.borders().right <= 0.0,
+            Moving::Up => self.borders().bottom + tail <= 0.0,
+            Moving::Right => self.borders().left - tail >= window_size,
+            Moving::Down => self.borders().top - tail >= window_size,
+            Moving::Left => self.borders().right + tail <= 0.0,
         }
     }
 }
 
-fn get_entry_coords(p: &Sector, direction: &Direction) -> (f32, f32) {
+fn get_entry_coords(p: &Sector, direction: &Direction, width: f32) -> (f32, f32) {
     match direction {
         Direction::West => (
-            SECTOR_WIDTH * p.get_x() as f32 - SECTOR_WIDTH,
-            SECTOR_WIDTH * p.get_y() as f32,
+            width * p.get_x() as f32 - width,
+            width * p.get_y() as f32,
         ),
         Direction::East => (
-            SECTOR_WIDTH * p.get_x() as f32 + SECTOR_WIDTH,
-            SECTOR_WIDTH * p.get_y() as f32,
+            width * p.get_x() as f32 + width,
+            width * p.get_y() as f32,
         ),
         Direction::North => (
-            SECTOR_WIDTH * p.get_x() as f32,
-            SECTOR_WIDTH * p.get_y() as f32 - SECTOR_WIDTH,
+            width * p.get_x() as f32,
+            width * p.get_y() as f32 - width,
         ),
         Direction::South => (
-            SECTOR_WIDTH * p.get_x() as f32,
-            SECTOR_WIDTH * p.get_y() as f32 + SECTOR_WIDTH,
+            width * p.get_x() as f32,
+            width * p.get_y() as f32 + width,
         ),
     }
 }
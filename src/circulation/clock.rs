@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::FPS;
+
+/// Horloge de la simulation : un compteur de ticks de durée fixe `1 / FPS`.
+///
+/// Toutes les durées mesurées dans `circulation` en dérivent, elles ne
+/// dépendent donc ni de la cadence d'affichage ni de la charge de la machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Clock {
+    tick: u64,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock { tick: 0 }
+    }
+
+    /// Durée d'un tick, en secondes.
+    pub fn dt() -> f32 {
+        1.0 / FPS as f32
+    }
+
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+
+    /// Nombre de ticks écoulés depuis le début de la simulation.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Temps simulé écoulé, en secondes.
+    pub fn elapsed(&self) -> f32 {
+        self.tick as f32 * Clock::dt()
+    }
+
+    /// Temps simulé écoulé depuis `tick`, en secondes.
+    pub fn since(&self, tick: u64) -> f32 {
+        self.tick.saturating_sub(tick) as f32 * Clock::dt()
+    }
+}
+
+/// Vitesses de simulation extrêmes, en multiples du temps réel.
+const MIN_TIME_SCALE: f32 = 0.25;
+const MAX_TIME_SCALE: f32 = 16.0;
+
+/// Cadence de la simulation dans la fenêtre : pause, pas à pas et
+/// accélération. La boucle d'affichage appelle `State::update` `ticks()`
+/// fois par image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playback {
+    pub paused: bool,
+    /// Multiple du temps réel, entre `MIN_TIME_SCALE` et `MAX_TIME_SCALE`.
+    time_scale: f32,
+    /// Ticks demandés pendant la pause.
+    steps: u32,
+    /// Fraction de tick reportée d'une image à l'autre, quand `time_scale` < 1.
+    budget: f32,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            paused: false,
+            time_scale: 1.0,
+            steps: 0,
+            budget: 0.0,
+        }
+    }
+}
+
+impl Playback {
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Multiplie la vitesse par `factor`.
+    pub fn scale(&mut self, factor: f32) {
+        self.time_scale = (self.time_scale * factor).clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+        self.budget = 0.0;
+    }
+
+    /// Demande un tick de plus pendant la pause.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.steps += 1;
+        }
+    }
+
+    /// Nombre de ticks à simuler pendant cette image.
+    pub fn ticks(&mut self) -> u32 {
+        if self.paused {
+            return std::mem::take(&mut self.steps);
+        }
+        self.budget += self.time_scale;
+        let ticks = self.budget.floor();
+        self.budget -= ticks;
+        ticks as u32
+    }
+}
@@ -1,67 +1,89 @@
-use crate::config::{
-    ACCELERATION_DISTANCE, CRUISE_SPEED, MARGIN, SCAN_DISTANCE, SECTOR_WIDTH, WINDOW_SIZE,
-};
+use crate::config::SimulationConfig;
 use crate::circulation::*;
 
 impl Car {
     /// ### forward_scan
     /// Scans the sectors in front of the car and accelerate depending on the distance
     /// to the closest car in front
-    pub fn forward_scan(&mut self, cars: &[Car]) {
+    pub fn forward_scan(&mut self, cars: &[Car], config: &SimulationConfig) {
+        // The longest distance to car in front.
+        let distance = self
+            .car_ahead(cars, config)
+            .map_or(config.window_size as f32, |(_, distance)| distance);
+        if distance > config.acceleration_distance_px() {
+            self.accelerate(distance, config);
+        } else {
+            self.brake(distance, config);
+        }
+    }
+
+    /// Closest car in the band scanned by `forward_scan`, with its distance.
+    pub fn car_ahead<'a>(
+        &self,
+        cars: &'a [Car],
+        config: &SimulationConfig,
+    ) -> Option<(&'a Car, f32)> {
         // Get the ranges where we scan cars in front
-        let scan_x = self.borders().left + MARGIN..=self.borders().right - MARGIN;
-        let scan_y = self.borders().top + MARGIN..=self.borders().bottom - MARGIN;
+        let margin = config.margin;
+        let scan_x = self.borders().left + margin..=self.borders().right - margin;
+        let scan_y = self.borders().top + margin..=self.borders().bottom - margin;
+
+        // A car whose center is beside the scanned band may still overhang it,
+        // e.g. one that is leaving the sector in front sideways.
+        let head = self.rects()[0];
+        let overhangs = |car: &Car, horizontal: bool| {
+            let (min, max) = extent(&[head], horizontal);
+            let (other_min, other_max) = extent(&car.rects(), horizontal);
+            other_min < max && other_max > min
+        };
 
-        // The longest distance to car in front.
-        let mut distance = WINDOW_SIZE as f32;
+        let mut distance = config.window_size as f32;
+        let mut ahead = None;
         for car in cars.iter().filter(|c| c.id != self.id) {
             if self.calc_dist(car) > distance {
                 continue;
             }
 
-            let (x, y) = car.center_car();
+            let (x, y) = self.nearest_center(car);
             let (self_x, self_y) = self.center_car();
-            match self.moving {
-                Moving::Up => {
-                    if y < self_y && scan_x.contains(&x) {
-                        distance = self.calc_dist(car);
-                    }
-                }
-                Moving::Down => {
-                    if y > self_y && scan_x.contains(&x) {
-                        distance = self.calc_dist(car);
-                    }
-                }
-                Moving::Right => {
-                    if x > self_x && scan_y.contains(&y) {
-                        distance = self.calc_dist(car);
-                    }
-                }
-                Moving::Left => {
-                    if x < self_x && scan_y.contains(&y) {
-                        distance = self.calc_dist(car);
-                    }
-                }
+            let in_front = match self.moving {
+                Moving::Up => y < self_y && (scan_x.contains(&x) || overhangs(car, true)),
+                Moving::Down => y > self_y && (scan_x.contains(&x) || overhangs(car, true)),
+                Moving::Right => x > self_x && (scan_y.contains(&y) || overhangs(car, false)),
+                Moving::Left => x < self_x && (scan_y.contains(&y) || overhangs(car, false)),
+            };
+            if in_front {
+                distance = self.calc_dist(car);
+                ahead = Some((car, distance));
             }
         }
-        if distance > ACCELERATION_DISTANCE {
-            self.accelerate(distance);
-        } else {
-            self.brake(distance);
-        }
+        ahead
     }
 
     
-  /* Vérifie s'il y a des voitures devant soi dans la `SCAN_DISTANCE`.
+  /* Vérifie s'il y a des voitures devant soi dans la `scan_distance`.
 Si ces voitures ont une distance plus courte vers la sortie que soi-même, freine selon la plus proche
  de ces voitures.*/
 
-    pub fn ray_casting(&mut self, cars: &[Car]) {
+    pub fn ray_casting(&mut self, cars: &[Car], config: &SimulationConfig) {
+        if let Some((_, distance)) = self.crossing_car(cars, config) {
+            self.brake(distance, config);
+        }
+    }
+
+    /// Voiture la plus proche pour laquelle `ray_casting` freine, avec sa distance.
+    pub fn crossing_car<'a>(
+        &self,
+        cars: &'a [Car],
+        config: &SimulationConfig,
+    ) -> Option<(&'a Car, f32)> {
         // Parcourir toutes les voitures qui sont dans la portée de collision (une secteur).
-        let mut distance = SCAN_DISTANCE;
+        let scan_distance = config.scan_distance_px();
+        let mut distance = scan_distance;
+        let mut crossing = None;
         for car in cars.iter().filter(|c| {
             self.longer_distance_to_exit(c)
-                && self.calc_dist(c) < SCAN_DISTANCE
+                && self.calc_dist(c) < scan_distance
                 && self.crossing_paths(c)
         }) {
             // Freiner uniquement en fonction de la distance la plus courte.
@@ -70,38 +92,23 @@ Si ces voitures ont une distance plus courte vers la sortie que soi-même, frein
             }
 
             let (x, y) = self.center_car();
-            let (x2, y2) = car.center_car();
-
-            match self.moving {
-                Moving::Up => {
-                    if y > y2 {
-                        distance = self.calc_dist(car);
-                    }
-                }
-                Moving::Down => {
-                    if y < y2 {
-                        distance = self.calc_dist(car);
-                    }
-                }
-                Moving::Right => {
-                    if x < x2 {
-                        distance = self.calc_dist(car);
-                    }
-                }
-                Moving::Left => {
-                    if x > x2 {
-                        distance = self.calc_dist(car);
-                    }
-                }
+            let (x2, y2) = self.nearest_center(car);
+
+            let behind = match self.moving {
+                Moving::Up => y > y2,
+                Moving::Down => y < y2,
+                Moving::Right => x < x2,
+                Moving::Left => x > x2,
+            };
+            if behind {
+                distance = self.calc_dist(car);
+                crossing = Some((car, distance));
             }
         }
-
-        if distance < SCAN_DISTANCE {
-            self.brake(distance);
-        }
+        crossing
     }
 
-    pub fn check_passing(&mut self, cars: &[Car]) {
+    pub fn check_passing(&mut self, cars: &[Car], config: &SimulationConfig) {
         let index = if self.turning == Turning::Straight {
             6..=8
         } else {
@@ -111,28 +118,30 @@ Si ces voitures ont une distance plus courte vers la sortie que soi-même, frein
             c.id != self.id
                 && c.turning == Turning::Straight
                 && self.direction != c.direction
-                && self.calc_dist(c) < SCAN_DISTANCE
-                && index.contains(&c.index)
+                && self.calc_dist(c) < config.scan_distance_px()
+                && c.occupied_indices().any(|i| index.contains(&i))
         }) {
             self.stop();
         }
     }
 
-    pub fn sector_in_front(&mut self, cars: &[Car]) {
+    pub fn sector_in_front(&mut self, cars: &[Car], config: &SimulationConfig) {
         if let Some(car) = cars
             .iter()
-            .find(|c| c.id != self.id && self.sector(1).eq(&c.sector(0)))
+            .find(|c| c.id != self.id && c.occupies(&self.sector(1)))
         {
-            self.brake(self.calc_dist(car));
+            self.brake(self.calc_dist(car), config);
         }
     }
 
 
     /// Vérifier si une voiture croise le chemin de la voiture actuelle.
     fn crossing_paths(&self, other: &Car) -> bool {
-        for sector in &self.path.sectors[self.index..=self.index + 2] {
-            if sector.eq(&other.sector(1))
-                || (sector.eq(&other.sector(0)) && other.sector_pos() < SECTOR_WIDTH / 2.0)
+        let last = self.path.sectors.len() - 1;
+        for sector in &self.path.sectors[self.index.min(last)..=(self.index + 2).min(last)] {
+            if other.path.sectors.get(other.index + 1) == Some(sector)
+                || (sector.eq(&other.sector(0)) && other.sector_pos() < other.sector_width() / 2.0)
+                || other.occupied_sectors().iter().any(|s| s.eq(sector))
             {
                 return true;
             }
@@ -142,18 +151,23 @@ Si ces voitures ont une distance plus courte vers la sortie que soi-même, frein
 
     /// Vérifie si self a une distance plus longue jusqu'à la sortie que other.
     fn longer_distance_to_exit(&self, other: &Car) -> bool {
-        self.path.sectors.len() as f32 * SECTOR_WIDTH
-            - (self.index as f32 * SECTOR_WIDTH + self.sector_pos())
-            > other.path.sectors.len() as f32 * SECTOR_WIDTH
-                - (other.index as f32 * SECTOR_WIDTH + other.sector_pos())
+        let width = self.sector_width();
+        self.path.sectors.len() as f32 * width
+            - (self.index as f32 * width + self.sector_pos())
+            > other.path.sectors.len() as f32 * width
+                - (other.index as f32 * width + other.sector_pos())
     }
 
-    pub fn center_scan(&mut self, cars: &[Car]) {
+    pub fn center_scan(&mut self, cars: &[Car], config: &SimulationConfig) {
         if cars
             .iter()
-            .any(|c| self.id < c.id && (5..=7).contains(&c.index) && c.turning == Turning::Left)
+            .any(|c| {
+                self.id < c.id
+                    && c.occupied_indices().any(|i| (5..=7).contains(&i))
+                    && c.turning == Turning::Left
+            })
         {
-            self.vel = CRUISE_SPEED;
+            self.vel = config.cruise_speed;
         }
     }
 
@@ -167,12 +181,41 @@ Si ces voitures ont une distance plus courte vers la sortie que soi-même, frein
         (left + ((right - left) / 2.0), top + ((bottom - top) / 2.0))
     }
 
-    /// Utilisé pour calculer la distance entre deux voitures. Les points centraux des deux voitures sont utilisés, puis la formule de distance est appliquée :
+    /// Centre de la case de `other` la plus proche de la voiture actuelle : sa
+    /// case de tête, ou l'une des suivantes pour un véhicule long.
+    fn nearest_center(&self, other: &Car) -> (f32, f32) {
+        let (x, y) = self.center_car();
+        let half = other.sector_width() / 2.0;
+        other
+            .cells()
+            .into_iter()
+            .map(|((x2, y2), _)| (x2 + half, y2 + half))
+            .min_by(|a, b| {
+                let da = (a.0 - x).powi(2) + (a.1 - y).powi(2);
+                let db = (b.0 - x).powi(2) + (b.1 - y).powi(2);
+                da.total_cmp(&db)
+            })
+            .unwrap_or_else(|| other.center_car())
+    }
+
+    /// Utilisé pour calculer la distance entre deux voitures. Le point central de la voiture actuelle et
+    /// celui de la case la plus proche de `other` sont utilisés, puis la formule de distance est appliquée :
     /// `sqrt(dx^2 + dy^2)`
     pub fn calc_dist(&self, other: &Car) -> f32 {
         let (x, y) = self.center_car();
-        let (x2, y2) = other.center_car();
+        let (x2, y2) = self.nearest_center(other);
         let (dx, dy) = ((x - x2).abs(), (y - y2).abs());
         (dx * dx + dy * dy).sqrt()
     }
 }
+
+/// Étendue des emprises `rects` sur l'axe x si `horizontal`, sur l'axe y sinon.
+fn extent(rects: &[OrientedRect], horizontal: bool) -> (f32, f32) {
+    rects
+        .iter()
+        .flat_map(OrientedRect::corners)
+        .map(|(x, y)| if horizontal { x } else { y })
+        .fold((f32::MAX, f32::MIN), |(min, max), v| {
+            (min.min(v), max.max(v))
+        })
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::reservation::ReservationManager;
+use crate::circulation::stop_sign::StopSign;
+use crate::circulation::traffic_light::{Signal, TrafficLights};
+use crate::circulation::{Car, Clock, Direction, Sector, Statistics, Turning};
+use crate::config::SimulationConfig;
+
+/// Ce qu'un contrôleur peut consulter pendant un tick.
+pub struct TickContext<'a> {
+    pub config: &'a SimulationConfig,
+    pub clock: &'a Clock,
+}
+
+/// Stratégie de gestion de l'intersection.
+///
+/// À chaque tick, `State::update` appelle `begin_tick` une fois, puis pour
+/// chaque voiture `may_advance` et, si elle peut avancer, `drive`, ou
+/// `preempt` si elle cède le passage à un véhicule d'urgence. Toutes les
+/// méthodes reçoivent la liste des voitures telle qu'elle était au début du tick.
+pub trait IntersectionController: fmt::Debug {
+    /// Nom court, utilisé dans les rapports.
+    fn name(&self) -> &'static str;
+
+    /// Planification globale, avant que les voitures ne bougent.
+    fn begin_tick(&mut self, _cars: &[Car], _ctx: &TickContext, _stats: &mut Statistics) {}
+
+    /// `false` si `car` doit rester immobile pendant ce tick.
+    fn may_advance(&self, _car: &Car, _cars: &[Car], _ctx: &TickContext) -> bool {
+        true
+    }
+
+    /// Ajuste la vitesse de `car` et le fait avancer d'un tick.
+    fn drive(&mut self, car: &mut Car, cars: &[Car], ctx: &TickContext);
+
+    /// `car` cède le passage à un véhicule d'urgence pendant ce tick, à la
+    /// place de `drive` : le contrôleur lui retire les autorisations de
+    /// traverser qu'il lui a données.
+    fn preempt(&mut self, _car: &Car) {}
+
+    /// État du feu d'un mouvement, pour l'affichage. `None` sans feux.
+    fn signal(&self, _direction: &Direction, _turning: &Turning) -> Option<Signal> {
+        None
+    }
+
+    /// État interne, pour `State::snapshot`. `None` pour un contrôleur sans
+    /// état : `State::restore` le reconstruit d'après `config.controller`.
+    fn snapshot(&self) -> Option<ControllerSnapshot> {
+        None
+    }
+}
+
+/// État sauvegardé d'un contrôleur, voir `IntersectionController::snapshot`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerSnapshot {
+    Reservation(ReservationManager),
+    TrafficLights(TrafficLights),
+    StopSign(StopSign),
+}
+
+impl ControllerSnapshot {
+    /// Contrôleur dont c'est l'état.
+    pub fn kind(&self) -> ControllerKind {
+        match self {
+            ControllerSnapshot::Reservation(_) => ControllerKind::Reservation,
+            ControllerSnapshot::TrafficLights(_) => ControllerKind::TrafficLights,
+            ControllerSnapshot::StopSign(_) => ControllerKind::StopSign,
+        }
+    }
+
+    pub fn restore(self) -> Box<dyn IntersectionController> {
+        match self {
+            ControllerSnapshot::Reservation(manager) => Box::new(manager),
+            ControllerSnapshot::TrafficLights(lights) => Box::new(lights),
+            ControllerSnapshot::StopSign(stop) => Box::new(stop),
+        }
+    }
+}
+
+/// Contrôleurs disponibles, sélectionnés par `SimulationConfig::controller`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerKind {
+    #[default]
+    Reactive,
+    Reservation,
+    TrafficLights,
+    StopSign,
+}
+
+impl ControllerKind {
+    pub const ALL: [ControllerKind; 4] = [
+        ControllerKind::Reactive,
+        ControllerKind::Reservation,
+        ControllerKind::TrafficLights,
+        ControllerKind::StopSign,
+    ];
+
+    pub fn build(&self, config: &SimulationConfig) -> Box<dyn IntersectionController> {
+        match self {
+            ControllerKind::Reactive => Box::new(Reactive),
+            ControllerKind::Reservation => Box::new(ReservationManager::new()),
+            ControllerKind::TrafficLights => Box::new(TrafficLights::new(config.signals.clone())),
+            ControllerKind::StopSign => Box::new(StopSign::new()),
+        }
+    }
+}
+
+/// Conduite commune aux contrôleurs centralisés : la voiture suit celle qui la
+/// précède et, si `hold` est vrai, s'arrête à la ligne de l'intersection.
+pub fn follow_lane(car: &mut Car, cars: &[Car], config: &SimulationConfig, hold: bool) {
+    car.forward_scan(cars, config);
+    if hold {
+        car.stop_before(car.path.entry_index(), config);
+    }
+    car.advance(cars, config);
+    if car.index > 0 {
+        car.adjust_position();
+    }
+}
+
+/// Chaque voiture évite les autres avec ses propres capteurs : balayage des
+/// secteurs, `ray_casting`, `check_passing` et `center_scan`. Les impasses
+/// au centre de l'intersection sont résolues en arrêtant certains virages à gauche.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reactive;
+
+impl IntersectionController for Reactive {
+    fn name(&self) -> &'static str {
+        "reactive"
+    }
+
+    fn may_advance(&self, car: &Car, cars: &[Car], ctx: &TickContext) -> bool {
+        !detect_deadlock(cars, car, ctx.config)
+    }
+
+    fn drive(&mut self, car: &mut Car, cars: &[Car], ctx: &TickContext) {
+        car.move_car(cars, ctx.config);
+    }
+}
+
+fn detect_deadlock(other_cars: &[Car], car: &Car, config: &SimulationConfig) -> bool {
+    if car.turning != Turning::Left {
+        return false;
+    }
+
+    let middle_sectors = [(5, 5), (5, 6), (6, 5), (6, 6)];
+    let stop_line = config.sector_width() - config.margin;
+    // Virages à gauche qui ont passé la ligne de l'index 4 : quand plusieurs
+    // voitures y arrivent au même tick, les plus anciennes passent d'abord.
+    let committed = |c: &&Car| {
+        c.id < car.id
+            && c.turning == Turning::Left
+            && c.index == 4
+            && c.sector_pos() > stop_line
+            && c.vel > 0.0
+    };
+    let in_middle = |s: &Sector| middle_sectors.contains(&(s.get_x(), s.get_y()));
+    let cars: Vec<&Car> = other_cars
+        .iter()
+        .filter(|c| c.occupied_sectors().iter().any(in_middle))
+        .collect();
+    // Secteurs du centre occupés, ou bientôt occupés : un véhicule long occupe
+    // autant de secteurs qu'il lui en reste à traverser, dans la limite de sa
+    // longueur.
+    let occupied = cars
+        .iter()
+        .map(|c| {
+            let ahead = c.path.sectors[c.index.min(c.path.sectors.len() - 1)..]
+                .iter()
+                .filter(|s| in_middle(s))
+                .count();
+            c.span().min(ahead.max(1))
+        })
+        .chain(other_cars.iter().filter(committed).map(Car::span))
+        .sum::<usize>()
+        + car.span();
+
+    if car.index == 3 && car.sector_pos() > stop_line {
+        return occupied > 2;
+    }
+
+    if car.index == 4 && car.sector_pos() > stop_line {
+        let north = cars
+            .iter()
+            .filter(|c| c.direction == Direction::North)
+            .count();
+        let east = cars
+            .iter()
+            .filter(|c| c.direction == Direction::East)
+            .count();
+        let south = cars
+            .iter()
+            .filter(|c| c.direction == Direction::South)
+            .count();
+        let west = cars
+            .iter()
+            .filter(|c| c.direction == Direction::West)
+            .count();
+        match car.direction {
+            Direction::West => {
+                if north >= 2 || east >= 2 || south >= 2 {
+                    return false;
+                }
+            }
+            Direction::South => {
+                if north >= 2 || east >= 2 || west >= 2 {
+                    return false;
+                }
+            }
+            Direction::North => {
+                if west >= 2 || east >= 2 || south >= 2 {
+                    return false;
+                }
+            }
+            Direction::East => {
+                if north >= 2 || west >= 2 || south >= 2 {
+                    return false;
+                }
+            }
+        }
+        return occupied > 3;
+    }
+    false
+}
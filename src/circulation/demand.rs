@@ -0,0 +1,212 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::{Direction, Turning};
+
+/// Une valeur par approche.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PerApproach<T> {
+    pub north: T,
+    pub east: T,
+    pub south: T,
+    pub west: T,
+}
+
+impl<T: Clone> PerApproach<T> {
+    pub fn uniform(value: T) -> PerApproach<T> {
+        PerApproach {
+            north: value.clone(),
+            east: value.clone(),
+            south: value.clone(),
+            west: value,
+        }
+    }
+}
+
+impl<T> PerApproach<T> {
+    pub fn get(&self, direction: &Direction) -> &T {
+        match direction {
+            Direction::North => &self.north,
+            Direction::East => &self.east,
+            Direction::South => &self.south,
+            Direction::West => &self.west,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (Direction, &T)> {
+        [
+            (Direction::North, &self.north),
+            (Direction::East, &self.east),
+            (Direction::South, &self.south),
+            (Direction::West, &self.west),
+        ]
+        .into_iter()
+    }
+}
+
+/// Répartition des voitures d'une approche entre ses trois voies. Les poids
+/// sont relatifs : `{ left = 1, straight = 2, right = 1 }` envoie la moitié
+/// des voitures tout droit.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TurningSplit {
+    pub left: f32,
+    pub straight: f32,
+    pub right: f32,
+}
+
+impl TurningSplit {
+    pub fn total(&self) -> f32 {
+        self.left + self.straight + self.right
+    }
+
+    /// Tire un virage selon les poids. Un virage de poids nul n'est jamais tiré.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Turning {
+        let weights = [
+            (Turning::Left, self.left),
+            (Turning::Straight, self.straight),
+            (Turning::Right, self.right),
+        ];
+        let mut x = rng.gen::<f32>() * self.total();
+        let mut last = Turning::Straight;
+        for (turning, weight) in weights.into_iter().filter(|(_, w)| *w > 0.0) {
+            if x < weight {
+                return turning;
+            }
+            x -= weight;
+            last = turning;
+        }
+        // Les arrondis ont porté `x` au-delà du dernier poids.
+        last
+    }
+}
+
+/// Point du profil de demande : à `time` secondes, les débits sont
+/// multipliés par `factor`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfilePoint {
+    pub time: f32,
+    pub factor: f32,
+}
+
+/// Modèle de demande, lu depuis la section `demand` de la configuration.
+///
+/// Les voitures de chaque approche arrivent selon un processus de Poisson de
+/// débit `rates`, modulé au cours du temps par `profile`. Chacune prend un
+/// virage tiré selon la répartition `turning` de son approche, qui forme avec
+/// les débits une matrice origine-destination.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Demand {
+    /// Remplace le mode aléatoire, qui ajoute une voiture toutes les
+    /// `random_interval` millisecondes.
+    pub enabled: bool,
+    /// Débit moyen de chaque approche, en voitures par minute.
+    pub rates: PerApproach<f32>,
+    pub turning: PerApproach<TurningSplit>,
+    /// Facteur appliqué aux débits, interpolé linéairement entre les points
+    /// et constant avant le premier et après le dernier. Sans point, il vaut 1.
+    pub profile: Vec<ProfilePoint>,
+}
+
+impl Default for Demand {
+    fn default() -> Self {
+        // La charge du mode aléatoire : une voiture toutes les 750 ms.
+        Demand {
+            enabled: false,
+            rates: PerApproach::uniform(20.0),
+            turning: PerApproach::uniform(TurningSplit {
+                left: 1.0,
+                straight: 1.0,
+                right: 1.0,
+            }),
+            profile: Vec::new(),
+        }
+    }
+}
+
+impl Demand {
+    /// Facteur du profil à `time` secondes.
+    pub fn factor(&self, time: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.profile.first(), self.profile.last()) else {
+            return 1.0;
+        };
+        if time <= first.time {
+            return first.factor;
+        }
+        self.profile
+            .windows(2)
+            .find(|w| time < w[1].time)
+            .map_or(last.factor, |w| {
+                let t = (time - w[0].time) / (w[1].time - w[0].time);
+                w[0].factor + t * (w[1].factor - w[0].factor)
+            })
+    }
+
+    /// Débit de l'approche `direction` à `time` secondes, en voitures par minute.
+    pub fn rate(&self, direction: &Direction, time: f32) -> f32 {
+        self.rates.get(direction) * self.factor(time)
+    }
+
+    /// Tire les voitures arrivées pendant `dt` secondes à partir de `time`.
+    pub fn arrivals<R: Rng>(&self, time: f32, dt: f32, rng: &mut R) -> Vec<(Direction, Turning)> {
+        let mut arrivals = Vec::new();
+        for (direction, split) in self.turning.iter() {
+            let mean = self.rate(&direction, time) / 60.0 * dt;
+            for _ in 0..poisson(mean, rng) {
+                arrivals.push((direction.clone(), split.sample(rng)));
+            }
+        }
+        arrivals
+    }
+
+    /// Vérifie les contraintes que serde ne peut pas exprimer.
+    pub fn validate(&self) -> Result<(), String> {
+        if self
+            .rates
+            .iter()
+            .any(|(_, &r)| !(r >= 0.0 && r.is_finite()))
+        {
+            return Err("demand rates must be non-negative numbers".to_owned());
+        }
+        for (direction, split) in self.turning.iter() {
+            let weights = [split.left, split.straight, split.right];
+            if weights.iter().any(|&w| !(w >= 0.0 && w.is_finite())) || split.total() <= 0.0 {
+                return Err(format!(
+                    "turning split of {direction:?} must be non-negative and not all zero"
+                ));
+            }
+        }
+        if self
+            .profile
+            .iter()
+            .any(|p| !(p.factor >= 0.0 && p.factor.is_finite()))
+        {
+            return Err("demand profile factors must be non-negative numbers".to_owned());
+        }
+        if self.profile.iter().any(|p| !p.time.is_finite())
+            || self.profile.windows(2).any(|w| w[0].time >= w[1].time)
+        {
+            return Err("demand profile times must be increasing".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Tirage d'une loi de Poisson de moyenne `mean`, par la méthode de Knuth.
+/// Adaptée aux petites moyennes, celles d'un tick.
+fn poisson<R: Rng>(mean: f32, rng: &mut R) -> u32 {
+    if mean <= 0.0 {
+        return 0;
+    }
+    let limit = (-mean).exp();
+    let mut product = rng.gen::<f32>();
+    let mut count = 0;
+    while product > limit {
+        product *= rng.gen::<f32>();
+        count += 1;
+    }
+    count
+}
@@ -0,0 +1,79 @@
+use crate::circulation::controller::{follow_lane, TickContext};
+use crate::circulation::Car;
+
+fn has_entered(car: &Car) -> bool {
+    car.index > car.path.entry_index() || car.in_intersection()
+}
+
+/// `true` si `car` est un véhicule d'urgence qui n'a pas encore quitté
+/// l'intersection.
+fn is_responding(car: &Car) -> bool {
+    car.model.is_emergency() && !car.cleared_intersection()
+}
+
+/// Identifiant du véhicule d'urgence pour lequel `car` roule en priorité : le
+/// sien, ou celui qui la suit dans sa voie, qu'elle doit laisser passer.
+fn priority_of(car: &Car, cars: &[Car]) -> Option<usize> {
+    if car.model.is_emergency() {
+        return is_responding(car).then_some(car.id);
+    }
+    if car.cleared_intersection() {
+        return None;
+    }
+    cars.iter()
+        .filter(|e| {
+            is_responding(e)
+                && e.direction == car.direction
+                && e.turning == car.turning
+                && e.progress() < car.progress()
+        })
+        .map(|e| e.id)
+        .min()
+}
+
+/// `true` si `car` roule en priorité : c'est un véhicule d'urgence en
+/// intervention, ou une voiture qui le précède dans sa voie.
+pub fn has_priority(car: &Car, cars: &[Car]) -> bool {
+    priority_of(car, cars).is_some()
+}
+
+/// `true` si `car`, qui n'est pas encore entrée dans l'intersection, doit
+/// attendre à la ligne qu'un véhicule d'urgence dont le `Path` croise le sien
+/// en soit sorti. Entre deux véhicules d'urgence, le premier arrivé passe.
+///
+/// Le conflit se décide avec `Path::crosses` et non `Car::crossing_paths` :
+/// celui-ci ne compare que les secteurs que les deux voitures occupent ou
+/// s'apprêtent à occuper, trop tard pour s'arrêter à la ligne, alors qu'il
+/// faut savoir avant l'entrée si les deux trajets se couperont.
+pub fn must_yield(car: &Car, cars: &[Car]) -> bool {
+    if has_entered(car) {
+        return false;
+    }
+    let priority = priority_of(car, cars);
+    cars.iter().any(|e| {
+        is_responding(e)
+            && e.direction != car.direction
+            && priority.is_none_or(|id| e.id < id)
+            && car.path.crosses(&e.path)
+    })
+}
+
+/// Conduite d'une voiture prioritaire : elle ne tient pas compte du
+/// contrôleur, mais attend à la ligne que les voitures déjà engagées sur un
+/// `Path` qui croise le sien aient dégagé l'intersection.
+pub fn drive_with_priority(car: &mut Car, cars: &[Car], ctx: &TickContext) {
+    let hold = !has_entered(car)
+        && (must_yield(car, cars)
+            || cars.iter().any(|c| {
+                c.direction != car.direction
+                    && has_entered(c)
+                    && !c.cleared_intersection()
+                    && c.path.crosses(&car.path)
+            }));
+    follow_lane(car, cars, ctx.config, hold);
+}
+
+/// Conduite d'une voiture qui cède le passage : elle s'arrête à la ligne.
+pub fn give_way(car: &mut Car, cars: &[Car], ctx: &TickContext) {
+    follow_lane(car, cars, ctx.config, true);
+}
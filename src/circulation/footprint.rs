@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+/// Dimensions d'un modèle de voiture, en largeurs de secteur.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Footprint {
+    /// Dimension dans le sens de la marche.
+    pub length: f32,
+    pub width: f32,
+}
+
+/// Rectangle orienté : l'emprise d'une voiture sur la chaussée, en pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientedRect {
+    pub center: (f32, f32),
+    /// Vecteur unitaire dans le sens de la longueur.
+    pub axis: (f32, f32),
+    pub half_length: f32,
+    pub half_width: f32,
+}
+
+impl OrientedRect {
+    pub fn new(center: (f32, f32), axis: (f32, f32), length: f32, width: f32) -> OrientedRect {
+        let norm = (axis.0 * axis.0 + axis.1 * axis.1).sqrt();
+        OrientedRect {
+            center,
+            axis: (axis.0 / norm, axis.1 / norm),
+            half_length: length / 2.0,
+            half_width: width / 2.0,
+        }
+    }
+
+    /// Coins du rectangle, dans l'ordre du contour.
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (cx, cy) = self.center;
+        let (ax, ay) = self.axis;
+        let (lx, ly) = (ax * self.half_length, ay * self.half_length);
+        let (wx, wy) = (-ay * self.half_width, ax * self.half_width);
+        [
+            (cx + lx + wx, cy + ly + wy),
+            (cx + lx - wx, cy + ly - wy),
+            (cx - lx - wx, cy - ly - wy),
+            (cx - lx + wx, cy - ly + wy),
+        ]
+    }
+
+    /// Projection du rectangle sur `axis`, sous la forme `(min, max)`.
+    fn project(&self, axis: (f32, f32)) -> (f32, f32) {
+        self.corners()
+            .iter()
+            .map(|&(x, y)| x * axis.0 + y * axis.1)
+            .fold((f32::MAX, f32::MIN), |(min, max), p| (min.min(p), max.max(p)))
+    }
+
+    /// `true` si les deux rectangles se chevauchent. Des rectangles qui se
+    /// touchent seulement ne se chevauchent pas.
+    ///
+    /// Théorème de l'axe séparateur : deux rectangles sont disjoints s'il existe
+    /// un axe, parmi leurs quatre normales, sur lequel leurs projections sont
+    /// disjointes.
+    pub fn overlaps(&self, other: &OrientedRect) -> bool {
+        let axes = [
+            self.axis,
+            (-self.axis.1, self.axis.0),
+            other.axis,
+            (-other.axis.1, other.axis.0),
+        ];
+        axes.iter().all(|&axis| {
+            let (a_min, a_max) = self.project(axis);
+            let (b_min, b_max) = other.project(axis);
+            a_min < b_max && b_min < a_max
+        })
+    }
+
+    /// Plus petite distance entre les contours des deux rectangles, `0.0` s'ils
+    /// se chevauchent.
+    pub fn gap(&self, other: &OrientedRect) -> f32 {
+        if self.overlaps(other) {
+            return 0.0;
+        }
+        // Entre deux polygones convexes disjoints, la plus petite distance est
+        // atteinte entre un coin de l'un et un côté de l'autre.
+        let edges = |rect: &OrientedRect| {
+            let c = rect.corners();
+            [(c[0], c[1]), (c[1], c[2]), (c[2], c[3]), (c[3], c[0])]
+        };
+        let mut gap = f32::MAX;
+        for (a, b) in [(self, other), (other, self)] {
+            for corner in a.corners() {
+                for (start, end) in edges(b) {
+                    gap = gap.min(segment_distance(corner, start, end));
+                }
+            }
+        }
+        gap
+    }
+}
+
+/// Distance du point `p` au segment `[a, b]`.
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (x * x + y * y).sqrt()
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::{Car, Statistics};
+use crate::config::SimulationConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentKind {
+    /// Deux emprises séparées de moins de `close_call_gap`.
+    CloseCall,
+    /// Deux emprises qui se chevauchent, ou séparées de moins de `collision_gap`.
+    Collision,
+}
+
+/// Rapprochement de deux voitures, du tick où l'écart entre leurs emprises
+/// passe sous `close_call_gap` à celui où elles s'éloignent.
+///
+/// Un incident n'est compté qu'une fois, selon sa gravité maximale : un presque
+/// accident qui devient une collision compte comme une collision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    pub kind: IncidentKind,
+    /// Identifiants des deux voitures, le plus petit en premier.
+    pub cars: (usize, usize),
+    pub start_tick: u64,
+    /// Premier tick où les voitures sont de nouveau éloignées, `None` tant que
+    /// l'incident est en cours.
+    pub end_tick: Option<u64>,
+    /// Plus petit écart entre les emprises des deux voitures, en pixels.
+    pub min_gap: f32,
+    pub min_gap_tick: u64,
+    /// Positions `(x, y)` des deux voitures au tick `min_gap_tick`.
+    pub positions: [(f32, f32); 2],
+}
+
+/// Suit les paires de voitures proches d'un tick à l'autre.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct IncidentTracker {
+    /// Incidents en cours, par paire, avec leur index dans `Statistics::incidents`.
+    #[serde(with = "crate::circulation::snapshot::pairs")]
+    active: BTreeMap<(usize, usize), usize>,
+}
+
+impl IncidentTracker {
+    pub fn new() -> IncidentTracker {
+        IncidentTracker::default()
+    }
+
+    /// Compare toutes les paires de `cars` au tick `tick`, ouvre, met à jour ou
+    /// clôt les incidents correspondants dans `stats`.
+    pub fn update(
+        &mut self,
+        cars: &[Car],
+        tick: u64,
+        config: &SimulationConfig,
+        stats: &mut Statistics,
+    ) {
+        let mut active = BTreeMap::new();
+        for (i, a) in cars.iter().enumerate() {
+            for b in &cars[i + 1..] {
+                let gap = a.gap(b);
+                if gap > config.close_call_gap_px() {
+                    continue;
+                }
+                let kind = if gap < config.collision_gap_px() || a.overlaps(b) {
+                    IncidentKind::Collision
+                } else {
+                    IncidentKind::CloseCall
+                };
+                let (a, b) = if a.id < b.id { (a, b) } else { (b, a) };
+                let pair = (a.id, b.id);
+                let positions = [(a.x, a.y), (b.x, b.y)];
+
+                let index = match self.active.get(&pair) {
+                    Some(&index) => {
+                        let incident = stats.incident_mut(index);
+                        if kind == IncidentKind::Collision {
+                            incident.kind = kind;
+                        }
+                        if gap < incident.min_gap {
+                            incident.min_gap = gap;
+                            incident.min_gap_tick = tick;
+                            incident.positions = positions;
+                        }
+                        index
+                    }
+                    None => stats.add_incident(Incident {
+                        kind,
+                        cars: pair,
+                        start_tick: tick,
+                        end_tick: None,
+                        min_gap: gap,
+                        min_gap_tick: tick,
+                        positions,
+                    }),
+                };
+                active.insert(pair, index);
+            }
+        }
+
+        // Les paires qui ne sont plus proches, ou dont une voiture est sortie.
+        for (pair, index) in &self.active {
+            if !active.contains_key(pair) {
+                stats.incident_mut(*index).end_tick = Some(tick);
+            }
+        }
+        self.active = active;
+    }
+
+    /// Index dans `Statistics::incidents` des incidents en cours.
+    pub(crate) fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.active.values().copied()
+    }
+
+    /// `true` si `id` fait partie d'un incident en cours.
+    pub fn involves(&self, id: usize) -> bool {
+        self.active.keys().any(|&(a, b)| a == id || b == id)
+    }
+}
+
+/// Écrit le journal des incidents dans `path`, au format JSON.
+pub fn write_incidents(path: impl AsRef<Path>, incidents: &[Incident]) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(incidents).expect("incidents are always serializable");
+    fs::write(path, json)
+}
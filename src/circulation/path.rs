@@ -1,7 +1,14 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
 use crate::circulation::car::Turning;
 use crate::circulation::{Direction, Moving};
 
-#[derive(Eq, Clone, Debug)]
+/// Colonnes et lignes de la grille qui forment l'intersection, où les voies se croisent.
+pub const INTERSECTION: RangeInclusive<usize> = 3..=8;
+
+#[derive(Eq, Clone, Debug, Serialize, Deserialize)]
 pub struct Sector {
     x: usize,
     y: usize,
@@ -29,8 +36,12 @@ impl Sector {
     pub fn get_y(&self) -> usize {
         self.y
     }
+
+    pub fn in_intersection(&self) -> bool {
+        INTERSECTION.contains(&self.x) && INTERSECTION.contains(&self.y)
+    }
 }
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Path {
     pub sectors: Vec<Sector>,
 }
@@ -45,6 +56,30 @@ impl Path {
             },
         }
     }
+
+    /// Index du premier secteur de l'intersection.
+    pub fn entry_index(&self) -> usize {
+        self.sectors
+            .iter()
+            .position(Sector::in_intersection)
+            .unwrap_or(0)
+    }
+
+    /// `true` si les deux chemins partagent un secteur de l'intersection.
+    pub fn crosses(&self, other: &Path) -> bool {
+        self.sectors
+            .iter()
+            .filter(|s| s.in_intersection())
+            .any(|s| other.sectors.contains(s))
+    }
+
+    /// Index du dernier secteur de l'intersection.
+    pub fn exit_index(&self) -> usize {
+        self.sectors
+            .iter()
+            .rposition(Sector::in_intersection)
+            .unwrap_or(0)
+    }
 }
 
 fn left_turn(direction: &Direction) -> Vec<Sector> {
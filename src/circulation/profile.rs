@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::Footprint;
+use crate::config::{SimulationConfig, FPS};
+
+/// Capacités d'un modèle de voiture. Les vitesses sont dans l'unité de
+/// `Car::vel`, les accélérations en largeurs de secteur par seconde au carré.
+///
+/// Ces valeurs ne sont pas à l'échelle réelle. À pleine vitesse, une voiture
+/// parcourt quelques largeurs de secteur par seconde et ses capteurs ne voient
+/// qu'à `scan_distance` devant elle : la décélération est choisie pour qu'elle
+/// s'arrête dans ce champ, soit plusieurs dizaines de g si un secteur mesurait
+/// cinq mètres. Seuls les rapports entre modèles ont un sens physique.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VehicleProfile {
+    /// Facteur de la vitesse maximale : `top_speed` vaut `speed_limit * max_speed`
+    /// dans l'unité de `Car::vel`, soit `speed_limit² * max_speed` largeurs de
+    /// secteur par seconde.
+    pub max_speed: f32,
+    /// Accélération depuis l'arrêt sur une route dégagée, en largeurs de
+    /// secteur par seconde au carré.
+    pub max_acceleration: f32,
+    /// Plus forte décélération au freinage, en largeurs de secteur par seconde
+    /// au carré. Un arrêt d'urgence (`Car::stop`) reste immédiat.
+    pub max_deceleration: f32,
+    pub footprint: Footprint,
+    /// Délai avant de repartir une fois la voie libre, en secondes.
+    pub reaction_time: f32,
+}
+
+impl VehicleProfile {
+    /// Vitesse maximale de la voiture sur cette route, dans l'unité de `Car::vel`.
+    pub fn top_speed(&self, config: &SimulationConfig) -> f32 {
+        config.speed_limit * self.max_speed
+    }
+
+    /// Plus forte hausse de `Car::vel` en un tick, depuis l'arrêt.
+    pub fn acceleration_per_tick(&self, config: &SimulationConfig) -> f32 {
+        self.max_acceleration / config.speed_limit / FPS as f32
+    }
+
+    /// Plus forte baisse de `Car::vel` en un tick de freinage.
+    pub fn braking_per_tick(&self, config: &SimulationConfig) -> f32 {
+        self.max_deceleration / config.speed_limit / FPS as f32
+    }
+
+    /// `reaction_time` en ticks.
+    pub fn reaction_ticks(&self) -> u32 {
+        (self.reaction_time * FPS as f32).round() as u32
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::controller::{
+    follow_lane, ControllerSnapshot, IntersectionController, TickContext,
+};
+use crate::circulation::{Car, Statistics, Turning};
+use crate::config::{SimulationConfig, FPS};
+
+/// Vitesses essayées pour une demande, en fractions de `Car::top_speed`.
+const PLAN_SPEEDS: [f32; 4] = [1.0, 0.75, 0.5, 0.35];
+/// Marge ajoutée à la fin de chaque plage réservée, en ticks.
+const BUFFER_TICKS: u64 = 6;
+/// Délai entre deux demandes d'une même voiture, en ticks.
+const RETRY_TICKS: u64 = FPS / 10;
+/// Facteurs extrêmes appliqués à la vitesse par `Car::change_pos`.
+const FASTEST: f32 = 1.05;
+const SLOWEST: f32 = 0.90;
+
+/// Plage de ticks `[start, end]` pendant laquelle une voiture occupe un secteur.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Slot {
+    start: u64,
+    end: u64,
+    car: usize,
+}
+
+impl Slot {
+    fn overlaps(&self, other: &Slot) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// Gestionnaire central de réservations, dans l'esprit d'AIM.
+///
+/// Chaque voiture en tête de sa voie demande, avant d'entrer dans l'intersection,
+/// des plages de temps pour tous les secteurs de l'intersection sur son `Path`.
+/// La demande est acceptée si aucune plage ne chevauche celle d'une autre voiture,
+/// en essayant des vitesses de plus en plus basses. Une voiture sans réservation
+/// s'arrête à la ligne; une voiture réservée rejoint la vitesse prévue dans les
+/// limites de son modèle et la garde jusqu'à la sortie de l'intersection.
+///
+/// Deux plages accordées ne se chevauchent jamais sur un même secteur. Elles
+/// reposent cependant sur la vitesse prévue, que la voiture ne suit
+/// qu'approximativement : l'absence de collisions est vérifiée par les tests,
+/// elle n'est pas garantie par construction.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReservationManager {
+    #[serde(with = "crate::circulation::snapshot::pairs")]
+    table: BTreeMap<(usize, usize), Vec<Slot>>,
+    /// Vitesse accordée à chaque voiture réservée.
+    granted: BTreeMap<usize, f32>,
+    last_request: BTreeMap<usize, u64>,
+}
+
+impl ReservationManager {
+    pub fn new() -> ReservationManager {
+        ReservationManager::default()
+    }
+
+    pub fn is_granted(&self, car: &Car) -> bool {
+        self.granted.contains_key(&car.id)
+    }
+
+    /// Le seul secteur de l'intersection d'un virage à droite n'est partagé avec
+    /// aucune autre voie : ces voitures n'ont pas besoin de réservation. Les
+    /// véhicules d'urgence passent sans réservation, voir `emergency`.
+    fn needs_reservation(car: &Car) -> bool {
+        car.turning != Turning::Right && !car.model.is_emergency()
+    }
+
+    /// Plages d'occupation de chaque secteur de l'intersection pour `car`, à la vitesse `vel`.
+    fn plan(
+        &self,
+        car: &Car,
+        vel: f32,
+        now: u64,
+        config: &SimulationConfig,
+    ) -> Vec<((usize, usize), Slot)> {
+        let width = car.sector_width();
+        let step = vel * config.max_velocity();
+        let progress = car.progress();
+        let ticks = |distance: f32, speed: f32| (distance.max(0.0) / speed) as u64;
+        // La voiture n'est pas forcément à la vitesse prévue : au plus tôt, elle
+        // garde sa vitesse si elle roule plus vite; au plus tard, elle attend son
+        // temps de réaction puis accélère jusqu'à `vel`, ce qui la retarde de
+        // la moitié de la durée de la montée en vitesse.
+        let fastest = vel.max(car.vel) * config.max_velocity();
+        let profile = car.profile();
+        let missing = (vel - car.vel).max(0.0);
+        let ramp = missing / profile.acceleration_per_tick(config);
+        let reaction = if car.vel == 0.0 { profile.reaction_ticks() } else { 0 };
+        let delay = (ramp * missing / (2.0 * vel)).ceil() as u64 + reaction as u64;
+
+        (car.path.entry_index()..=car.path.exit_index())
+            .map(|k| {
+                let sector = &car.path.sectors[k];
+                let slot = Slot {
+                    start: now + ticks((k as f32 - 1.0) * width - progress, fastest * FASTEST),
+                    end: now
+                        + delay
+                        + ticks((k + car.span()) as f32 * width - progress, step * SLOWEST)
+                        + 1
+                        + BUFFER_TICKS,
+                    car: car.id,
+                };
+                ((sector.get_x(), sector.get_y()), slot)
+            })
+            .collect()
+    }
+
+    fn is_free(&self, plan: &[((usize, usize), Slot)]) -> bool {
+        plan.iter().all(|(sector, slot)| {
+            self.table
+                .get(sector)
+                .is_none_or(|slots| !slots.iter().any(|s| s.overlaps(slot)))
+        })
+    }
+
+    /// Essaie de réserver un passage pour `car`. Retourne `false` si toutes les vitesses sont refusées.
+    fn request(&mut self, car: &Car, now: u64, config: &SimulationConfig) -> bool {
+        for fraction in PLAN_SPEEDS {
+            let vel = car.top_speed(config) * fraction;
+            let plan = self.plan(car, vel, now, config);
+            if self.is_free(&plan) {
+                for (sector, slot) in plan {
+                    self.table.entry(sector).or_default().push(slot);
+                }
+                self.granted.insert(car.id, vel);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Libère les réservations des voitures sorties de l'intersection.
+    fn release(&mut self, cars: &[Car], now: u64) {
+        self.granted.retain(|id, _| {
+            cars.iter()
+                .any(|c| c.id == *id && !c.cleared_intersection())
+        });
+        self.last_request
+            .retain(|id, _| cars.iter().any(|c| c.id == *id));
+        for slots in self.table.values_mut() {
+            slots.retain(|s| s.end >= now);
+        }
+    }
+}
+
+impl IntersectionController for ReservationManager {
+    fn name(&self) -> &'static str {
+        "reservation"
+    }
+
+    fn begin_tick(&mut self, cars: &[Car], ctx: &TickContext, stats: &mut Statistics) {
+        let now = ctx.clock.tick();
+        self.release(cars, now);
+
+        for car in cars {
+            if !Self::needs_reservation(car)
+                || self.is_granted(car)
+                || car.index > car.path.entry_index()
+            {
+                continue;
+            }
+            let line = car.distance_to(car.path.entry_index() - 1);
+            if line > ctx.config.scan_distance_px() {
+                continue;
+            }
+            // Seule la première voiture de la voie qui attend peut réserver. Un
+            // véhicule prioritaire passe sans réservation : la voiture qui le
+            // suit attend qu'il ait dégagé l'intersection.
+            let leader = !cars.iter().any(|c| {
+                c.id < car.id
+                    && c.direction == car.direction
+                    && c.turning == car.turning
+                    && !self.is_granted(c)
+                    && !c.cleared_intersection()
+            });
+            let last = self.last_request.get(&car.id).copied();
+            if !leader || last.is_some_and(|t| now < t + RETRY_TICKS) {
+                continue;
+            }
+            self.last_request.insert(car.id, now);
+            if !self.request(car, now, ctx.config) {
+                stats.set_reservation_rejections();
+            }
+        }
+    }
+
+    fn drive(&mut self, car: &mut Car, cars: &[Car], ctx: &TickContext) {
+        match self.granted.get(&car.id) {
+            Some(&vel) => {
+                car.approach(vel, ctx.config);
+                // Les secteurs d'approche ne sont pas réservés : jusqu'à l'entrée
+                // de l'intersection, la voiture freine pour celle qui la précède.
+                if car.index < car.path.entry_index() {
+                    if let Some((_, distance)) = car.car_ahead(cars, ctx.config) {
+                        if distance <= ctx.config.acceleration_distance_px() {
+                            car.brake(distance, ctx.config);
+                        }
+                    }
+                }
+                car.advance(cars, ctx.config);
+                if car.index > 0 {
+                    car.adjust_position();
+                }
+            }
+            None => {
+                let hold = Self::needs_reservation(car) && car.index <= car.path.entry_index();
+                follow_lane(car, cars, ctx.config, hold);
+            }
+        }
+    }
+
+    fn preempt(&mut self, car: &Car) {
+        // Une voiture déjà engagée garde ses créneaux pour dégager l'intersection.
+        if car.in_intersection() || self.granted.remove(&car.id).is_none() {
+            return;
+        }
+        for slots in self.table.values_mut() {
+            slots.retain(|s| s.car != car.id);
+        }
+    }
+
+    fn snapshot(&self) -> Option<ControllerSnapshot> {
+        Some(ControllerSnapshot::Reservation(self.clone()))
+    }
+}
@@ -1,8 +1,11 @@
 use rand::prelude::IteratorRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::circulation::car::Car;
-use crate::circulation::{Direction, Statistics, Turning};
-#[derive(PartialEq, Debug, Clone)]
+use crate::circulation::{Clock, Direction, Statistics, Turning};
+use crate::config::SimulationConfig;
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Route {
     direction: Direction,
     pub cars: [Vec<Car>; 3],
@@ -24,7 +27,7 @@ impl Route {
         }
     }
 
-    pub fn get_available_path(&self) -> Option<Turning> {
+    pub fn get_available_path<R: Rng>(&self, rng: &mut R) -> Option<Turning> {
         let lanes = self.available_lanes();
         let mut paths = Vec::new();
 
@@ -48,53 +51,42 @@ impl Route {
             }
         }
 
-        paths.into_iter().choose(&mut rand::thread_rng())
+        paths.into_iter().choose(rng)
     }
 
-    fn available_lanes(&self) -> [bool; 3] {
-        let mut available = [false, false, false];
-
-        if self.cars[0].is_empty() {
-            available[0] = true;
-        } else {
-            let prev_car = self.cars[0].iter().next_back().unwrap();
-            if prev_car.index > 2 {
-                available[0] = true;
-            }
-        }
-
-        if self.cars[1].is_empty() {
-            available[1] = true;
-        } else {
-            let prev_car = self.cars[1].iter().next_back().unwrap();
-            if prev_car.index > 2 {
-                available[1] = true;
-            }
-        }
-
-        if self.cars[2].is_empty() {
-            available[2] = true;
-        } else {
-            let prev_car = self.cars[2].iter().next_back().unwrap();
-            if prev_car.index > 2 {
-                available[2] = true;
-            }
-        }
+    /// `true` si une voiture qui prend le virage `turning` peut apparaître.
+    pub fn lane_available(&self, turning: &Turning) -> bool {
+        let lane = match turning {
+            Turning::Left => 0,
+            Turning::Straight => 1,
+            Turning::Right => 2,
+        };
+        self.available_lanes()[lane]
+    }
 
-        available
+    /// Une voie est libre quand l'arrière de sa dernière voiture a dépassé le
+    /// secteur d'index 2.
+    fn available_lanes(&self) -> [bool; 3] {
+        self.cars.each_ref().map(|cars| {
+            cars.last()
+                .is_none_or(|prev_car| prev_car.index + 1 >= prev_car.span() + 3)
+        })
     }
 
     // Ajouter du temps pour toutes les voitures qui ont atteint leur destination, puis les supprimer du vecteur.
-    pub fn cleanup_cars(&mut self, stats: &mut Statistics) {
+    pub fn cleanup_cars(&mut self, stats: &mut Statistics, clock: &Clock, config: &SimulationConfig) {
         self.cars.iter().for_each(|cars| {
             cars.iter()
-                .filter(|car| car.is_done())
-                .for_each(|car| car.add_time(stats))
+                .filter(|car| car.is_done(config))
+                .for_each(|car| {
+                    car.add_time(stats, clock);
+                    stats.add_trip(car.trip_record(clock));
+                })
         });
 
-        self.cars[0].retain(|car| !car.is_done());
-        self.cars[1].retain(|car| !car.is_done());
-        self.cars[2].retain(|car| !car.is_done());
+        self.cars[0].retain(|car| !car.is_done(config));
+        self.cars[1].retain(|car| !car.is_done(config));
+        self.cars[2].retain(|car| !car.is_done(config));
         // self.cars[3].retain(|car| !car.is_done());
         
     }
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::{Direction, Model, Turning};
+
+/// Apparition programmée d'une voiture.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    /// Tick de `Clock` auquel la voiture est demandée. Si sa voie n'est pas
+    /// libre, elle attend dans la file de son approche, comme avec `State::add_car`.
+    pub tick: u64,
+    pub direction: Direction,
+    pub turning: Turning,
+    #[serde(default = "default_model")]
+    pub model: Model,
+    /// Vitesse initiale, dans l'unité de `Car::vel`, limitée à la vitesse
+    /// maximale du modèle. `1.0` si absente, comme pour les autres voitures.
+    pub velocity: Option<f32>,
+}
+
+fn default_model() -> Model {
+    Model::Standard
+}
+
+/// Trafic scripté : une liste d'apparitions, lue depuis un fichier TOML ou JSON.
+///
+/// ```toml
+/// description = "Quatre virages à gauche simultanés"
+///
+/// [[spawns]]
+/// tick = 0
+/// direction = "north"
+/// turning = "left"
+/// model = "garbage_truck"
+/// velocity = 0.5
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+}
+
+impl Scenario {
+    pub fn from_toml(content: &str) -> Result<Scenario, ScenarioError> {
+        let scenario: Scenario =
+            toml::from_str(content).map_err(|e| ScenarioError::Parse(e.to_string()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn from_json(content: &str) -> Result<Scenario, ScenarioError> {
+        let scenario: Scenario =
+            serde_json::from_str(content).map_err(|e| ScenarioError::Parse(e.to_string()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Charge un fichier `.toml` ou `.json` selon son extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Scenario, ScenarioError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| ScenarioError::Io(format!("{}: {e}", path.display())))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Scenario::from_json(&content),
+            _ => Scenario::from_toml(&content),
+        }
+    }
+
+    /// Vérifie les contraintes que serde ne peut pas exprimer.
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        for (i, spawn) in self.spawns.iter().enumerate() {
+            if spawn.velocity.is_some_and(|v| !(v >= 0.0 && v.is_finite())) {
+                return Err(ScenarioError::Invalid(format!(
+                    "spawn {i}: velocity must be a non-negative number"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScenarioError {
+    Io(String),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(msg) => write!(f, "cannot read scenario: {msg}"),
+            ScenarioError::Parse(msg) | ScenarioError::Invalid(msg) => {
+                write!(f, "invalid scenario: {msg}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::controller::ControllerSnapshot;
+use crate::circulation::road::Route;
+use crate::circulation::scenario::Spawn;
+use crate::circulation::state::QueuedCar;
+use crate::circulation::{Clock, IncidentTracker, Statistics};
+use crate::config::SimulationConfig;
+
+/// Copie d'un `State`, générateur aléatoire et horloge compris : l'état
+/// restauré par `State::restore` continue exactement comme l'original. Seule
+/// la fin des journaux de trajets et d'incidents est gardée, voir
+/// `Statistics::trimmed`.
+///
+/// Un instantané s'enregistre en JSON, lisible et modifiable à la main, ou
+/// en bincode, plus compact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) roads: [Route; 4],
+    pub(crate) stats: Statistics,
+    pub(crate) incidents: IncidentTracker,
+    pub(crate) clock: Clock,
+    pub(crate) random: bool,
+    pub(crate) total_cars: usize,
+    pub(crate) config: SimulationConfig,
+    /// `None` si le contrôleur n'a pas d'état, voir `IntersectionController::snapshot`.
+    pub(crate) controller: Option<ControllerSnapshot>,
+    pub(crate) seed: u64,
+    pub(crate) rng: ChaCha8Rng,
+    pub(crate) scheduled: Vec<Spawn>,
+    pub(crate) queues: [VecDeque<QueuedCar>; 4],
+}
+
+impl Snapshot {
+    /// Tick de l'horloge au moment de l'instantané.
+    pub fn tick(&self) -> u64 {
+        self.clock.tick()
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a snapshot is always serializable")
+    }
+
+    pub fn from_json(content: &str) -> Result<Snapshot, SnapshotError> {
+        let snapshot: Snapshot =
+            serde_json::from_str(content).map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    pub fn to_bincode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("a snapshot is always serializable")
+    }
+
+    pub fn from_bincode(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        let snapshot: Snapshot =
+            bincode::deserialize(bytes).map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    /// Vérifie un instantané lu depuis un fichier, qui a pu être modifié à la
+    /// main : la configuration doit être valide, l'état du contrôleur doit
+    /// correspondre à `config.controller`, les incidents en cours doivent
+    /// exister et chaque voiture doit être sur un secteur de son chemin.
+    fn validate(&self) -> Result<(), SnapshotError> {
+        self.config
+            .validate()
+            .map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        let expected = self.config.controller;
+        let matches = match &self.controller {
+            Some(controller) => controller.kind() == expected,
+            None => expected.build(&self.config).snapshot().is_none(),
+        };
+        if !matches {
+            return Err(SnapshotError::Parse(format!(
+                "controller state does not match controller `{expected:?}`"
+            )));
+        }
+        if let Some(index) = self.incidents.indices().find(|&i| !self.stats.has_incident(i)) {
+            return Err(SnapshotError::Parse(format!(
+                "incident {index} is active but not in the incident log"
+            )));
+        }
+        let mut cars = self.roads.iter().flat_map(|r| r.cars.iter().flatten());
+        if let Some(car) = cars.find(|c| c.index >= c.path.sectors.len()) {
+            return Err(SnapshotError::Parse(format!(
+                "car {} is at sector {} of a path of {} sectors",
+                car.id,
+                car.index,
+                car.path.sectors.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Écrit l'instantané en JSON si `path` finit par `.json`, en bincode sinon.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => fs::write(path, self.to_json()),
+            _ => fs::write(path, self.to_bincode()),
+        }
+    }
+
+    /// Lit un fichier écrit par `save`, selon son extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+        let path = path.as_ref();
+        let content =
+            fs::read(path).map_err(|e| SnapshotError::Io(format!("{}: {e}", path.display())))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Snapshot::from_json(&String::from_utf8_lossy(&content)),
+            _ => Snapshot::from_bincode(&content),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    Io(String),
+    Parse(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(msg) => write!(f, "cannot read snapshot: {msg}"),
+            SnapshotError::Parse(msg) => write!(f, "invalid snapshot: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Sérialise une `BTreeMap` comme une liste de paires : JSON n'accepte que
+/// des chaînes comme clés, pas des tuples.
+pub(crate) mod pairs {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|pairs| pairs.into_iter().collect())
+    }
+}
@@ -1,12 +1,27 @@
-use crate::config::{CLOSE_CALL_DISTANCE, COLLISION_DISTANCE, MARGIN, SECTOR_WIDTH};
-use macroquad::rand::gen_range;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::Path;
 
-use crate::circulation::car::Car;
+use crate::config::SimulationConfig;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::car::{Car, CarId};
+use crate::circulation::clock::{Clock, Playback};
+use crate::circulation::controller::{IntersectionController, TickContext};
+use crate::circulation::emergency;
+use crate::circulation::incident::IncidentTracker;
 use crate::circulation::road::Route;
+use crate::circulation::scenario::{Scenario, Spawn};
+use crate::circulation::snapshot::Snapshot;
 use crate::circulation::statistics::*;
-use crate::circulation::Turning;
+use crate::circulation::trace::{TraceHeader, TraceWriter};
+use crate::circulation::{Model, Turning};
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Direction {
     North,
     East,
@@ -14,17 +29,118 @@ pub enum Direction {
     West,
 }
 
-#[derive(PartialEq, Debug)]
+impl Direction {
+    /// Approche située à droite d'une voiture venant de `self`, qui a la
+    /// priorité sur elle.
+    pub fn on_right(&self) -> Direction {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South,
+        }
+    }
+
+    /// Index dans `State::roads` de la route qui vient de `self`.
+    pub fn road(&self) -> usize {
+        match self {
+            Direction::North => 0,
+            Direction::East => 1,
+            Direction::South => 2,
+            Direction::West => 3,
+        }
+    }
+}
+
+/// Raison pour laquelle `State::add_car_with` n'a pas ajouté de voiture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnError {
+    /// La voie demandée, ou toutes les voies pour un véhicule d'urgence, ne
+    /// sont pas libres.
+    LaneOccupied,
+    /// Trop de voitures sont arrêtées pour en ajouter d'autres.
+    TrafficJammed,
+    /// Des voitures attendent déjà dans la file de l'approche : elles passent
+    /// en premier.
+    QueueNotEmpty,
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpawnError::LaneOccupied => write!(f, "lane is not free"),
+            SpawnError::TrafficJammed => write!(f, "too many cars are stopped"),
+            SpawnError::QueueNotEmpty => write!(f, "cars are waiting in the approach's queue"),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
+#[derive(Debug)]
 pub struct State {
     pub roads: [Route; 4],
     pub stats: Statistics,
+    /// Paires de voitures trop proches, voir `Statistics::incidents`.
+    pub incidents: IncidentTracker,
+    pub clock: Clock,
     pub show_final_statistics: bool,
+    /// Affiche les capteurs des voitures, voir `representation_debug`.
+    pub show_debug: bool,
+    /// Affiche les statistiques pendant la simulation, voir `representation_hud`.
+    pub show_hud: bool,
     pub random: bool,
+    /// Pause et vitesse de la simulation dans la fenêtre.
+    pub playback: Playback,
     pub total_cars: usize,
+    pub config: SimulationConfig,
+    /// Stratégie de gestion de l'intersection, construite d'après `config.controller`.
+    pub controller: Box<dyn IntersectionController>,
+    /// Graine du générateur, à reporter pour rejouer une simulation à l'identique.
+    pub seed: u64,
+    rng: ChaCha8Rng,
+    /// Apparitions programmées pas encore faites, par tick croissant.
+    scheduled: Vec<Spawn>,
+    /// Voitures en attente hors de la fenêtre, par approche, dans l'ordre
+    /// `Direction::road`.
+    queues: [VecDeque<QueuedCar>; 4],
+    /// Trace en cours d'enregistrement, voir `State::record_trace`.
+    trace: Option<TraceWriter>,
+}
+
+/// Demande d'apparition en attente dans la file de son approche.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct QueuedCar {
+    direction: Direction,
+    /// Voie demandée; la première voie libre si `None`.
+    turning: Option<Turning>,
+    /// Modèle demandé; tiré au hasard si `None`.
+    #[serde(default)]
+    model: Option<Model>,
+    /// Vitesse initiale, voir `Spawn::velocity`.
+    #[serde(default)]
+    velocity: Option<f32>,
+    /// Tick de la demande.
+    tick: u64,
+}
+
+impl QueuedCar {
+    fn new(direction: Direction, turning: Option<Turning>, tick: u64) -> QueuedCar {
+        QueuedCar {
+            direction,
+            turning,
+            model: None,
+            velocity: None,
+            tick,
+        }
+    }
 }
 
 impl State {
-    pub fn new() -> State {
+    /// Crée un état à partir de `config`. Sans `config.seed`, la graine est
+    /// tirée au hasard, voir `State::seed`.
+    pub fn new(config: SimulationConfig) -> State {
+        let seed = config.seed.unwrap_or_else(rand::random);
         State {
             roads: [
                 Route::new(Direction::North),
@@ -33,171 +149,337 @@ impl State {
                 Route::new(Direction::West),
             ],
             stats: Statistics::default(),
+            incidents: IncidentTracker::new(),
+            clock: Clock::new(),
             random: false,
             total_cars: 0,
             show_final_statistics: false,
+            show_debug: false,
+            show_hud: true,
+            playback: Playback::default(),
+            controller: config.controller.build(&config),
+            config,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            scheduled: Vec::new(),
+            queues: Default::default(),
+            trace: None,
         }
     }
 
+    /// Crée un état avec la configuration par défaut, dont tous les tirages
+    /// aléatoires dérivent de `seed`.
+    pub fn with_seed(seed: u64) -> State {
+        State::new(SimulationConfig {
+            seed: Some(seed),
+            ..SimulationConfig::default()
+        })
+    }
+
     pub fn update(&mut self) {
+        self.spawn_scheduled();
+        for road in 0..self.queues.len() {
+            self.serve_queue(road);
+            self.stats.add_queue_length(self.queues[road].len());
+        }
+        self.stats.set_max_vehicles(self.total_cars);
         let all_cars = self.get_all_cars();
+        let ctx = TickContext {
+            config: &self.config,
+            clock: &self.clock,
+        };
+        self.controller.begin_tick(&all_cars, &ctx, &mut self.stats);
+
+        self.incidents
+            .update(&all_cars, self.clock.tick(), &self.config, &mut self.stats);
 
         self.roads.iter_mut().for_each(|road| {
             // Cleanup and statistics logic
-            road.cleanup_cars(&mut self.stats);
+            road.cleanup_cars(&mut self.stats, &self.clock, &self.config);
 
             // Iterating over each lane's cars
             road.cars.iter_mut().for_each(|cars| {
                 cars.iter_mut().for_each(|car| {
-                    if detect_collision(car, &all_cars) {
-                        self.stats.set_collisions()
-                    } else if detect_close_call(car, &all_cars) {
-                        self.stats.set_close_calls();
-                    }
-
-                    if detect_deadlock(&all_cars, car) {
+                    if !self.controller.may_advance(car, &all_cars, &ctx) {
                         car.stop();
-                        return;
+                    } else {
+                        self.stats.set_velocity(car.vel * self.config.sector_width());
+                        if emergency::must_yield(car, &all_cars) {
+                            self.controller.preempt(car);
+                            emergency::give_way(car, &all_cars, &ctx);
+                        } else if emergency::has_priority(car, &all_cars) {
+                            emergency::drive_with_priority(car, &all_cars, &ctx);
+                        } else {
+                            self.controller.drive(car, &all_cars, &ctx);
+                        }
                     }
-                    self.stats.set_velocity(car.vel);
-                    car.move_car(&all_cars);
+                    let close = self.incidents.involves(car.id);
+                    car.record_trip(self.clock.tick(), close, &self.config);
                 });
             });
         });
+
+        if let Some(mut trace) = self.trace.take() {
+            trace.write_frame(self.clock.tick(), &self.get_all_cars(), self.stats.incidents());
+            self.trace = Some(trace);
+        }
+        self.clock.advance();
     }
-    pub fn add_car(&mut self, direction: Direction) {
-        if self.get_all_cars().iter().filter(|c| c.vel == 0.0).count() >= 6 {
-            return;
+
+    /// Enregistre la suite de la simulation dans `path`, une ligne JSON par
+    /// tick, pour la rejouer avec `Replay`.
+    pub fn record_trace(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let header = TraceHeader {
+            seed: self.seed,
+            config: self.config.clone(),
+        };
+        self.trace = Some(TraceWriter::create(path, &header)?);
+        Ok(())
+    }
+
+    /// Termine l'enregistrement commencé par `record_trace`.
+    pub fn finish_trace(&mut self) -> io::Result<()> {
+        self.trace.take().map_or(Ok(()), TraceWriter::finish)
+    }
+
+    /// Copie l'état de la simulation, pour la reprendre plus tard avec
+    /// `State::restore`. La trace en cours n'en fait pas partie.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            roads: self.roads.clone(),
+            stats: self
+                .stats
+                .trimmed(self.clock.tick(), self.incidents.indices().min()),
+            incidents: self.incidents.clone(),
+            clock: self.clock,
+            random: self.random,
+            total_cars: self.total_cars,
+            config: self.config.clone(),
+            controller: self.controller.snapshot(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+            scheduled: self.scheduled.clone(),
+            queues: self.queues.clone(),
         }
-        match direction {
-            Direction::North => {
-                let available_path = self.roads[0].get_available_path();
-                if let Some(path) = available_path {
-                    self.roads[0].add_car(Car::new(direction, path, self.total_cars));
-                    self.total_cars += 1;
-                }
-            }
-            Direction::East => {
-                let available_path = self.roads[1].get_available_path();
+    }
 
-                if let Some(path) = available_path {
-                    self.roads[1].add_car(Car::new(direction, path, self.total_cars));
-                    self.total_cars += 1;
-                }
-            }
-            Direction::South => {
-                let available_path = self.roads[2].get_available_path();
-                if let Some(path) = available_path {
-                    self.roads[2].add_car(Car::new(direction, path, self.total_cars));
-                    self.total_cars += 1;
-                }
+    /// Reprend la simulation là où `snapshot` l'a copiée.
+    pub fn restore(snapshot: Snapshot) -> State {
+        let controller = match snapshot.controller {
+            Some(controller) => controller.restore(),
+            None => snapshot.config.controller.build(&snapshot.config),
+        };
+        State {
+            roads: snapshot.roads,
+            stats: snapshot.stats,
+            incidents: snapshot.incidents,
+            clock: snapshot.clock,
+            show_final_statistics: false,
+            show_debug: false,
+            show_hud: true,
+            random: snapshot.random,
+            playback: Playback::default(),
+            total_cars: snapshot.total_cars,
+            config: snapshot.config,
+            controller,
+            seed: snapshot.seed,
+            rng: snapshot.rng,
+            scheduled: snapshot.scheduled,
+            queues: snapshot.queues,
+            trace: None,
+        }
+    }
+    /// Demande une voiture venant de `direction`. Elle apparaît tout de suite
+    /// si une voie est libre, sinon elle attend dans la file de son approche.
+    pub fn add_car(&mut self, direction: Direction) {
+        self.request(QueuedCar::new(direction, None, self.clock.tick()));
+    }
+
+    /// Ajoute tout de suite une voiture de modèle `model` sur la voie `turning`
+    /// de `direction`. Contrairement à `add_car`, la demande n'attend pas dans
+    /// une file : elle échoue si la voie n'est pas libre, ou si des voitures
+    /// attendent déjà dans la file de `direction`. Un véhicule d'urgence est
+    /// ajouté même si le trafic est bloqué ou la file non vide.
+    pub fn add_car_with(
+        &mut self,
+        direction: Direction,
+        turning: Turning,
+        model: Model,
+    ) -> Result<CarId, SpawnError> {
+        if !model.is_emergency() {
+            if !self.queues[direction.road()].is_empty() {
+                return Err(SpawnError::QueueNotEmpty);
             }
-            Direction::West => {
-                let available_path = self.roads[3].get_available_path();
-                if let Some(path) = available_path {
-                    self.roads[3].add_car(Car::new(direction, path, self.total_cars));
-                    self.total_cars += 1;
-                }
+            if self.traffic_jammed() {
+                return Err(SpawnError::TrafficJammed);
             }
         }
+        self.spawn(direction, Some(turning), Some(model), None)
+            .ok_or(SpawnError::LaneOccupied)
     }
 
-    pub fn get_all_cars(&self) -> Vec<Car> {
-        let mut cars = Vec::new();
-        for r in self.roads.iter() {
-            for car in r.cars.clone().iter().take(2).flatten() {
-                if (1..11).contains(&car.index) {
-                    cars.push(car.clone());
-                }
-            }
+    /// `true` quand trop de voitures sont arrêtées pour en ajouter d'autres.
+    fn traffic_jammed(&self) -> bool {
+        self.get_all_cars().iter().filter(|c| c.vel == 0.0).count() >= 6
+    }
+
+    /// Tire les arrivées du tick courant selon `config.demand`. Comme avec
+    /// `add_car`, une arrivée dont la voie n'est pas libre attend dans la file
+    /// de son approche.
+    pub fn add_demand(&mut self) {
+        let arrivals =
+            self.config
+                .demand
+                .arrivals(self.clock.elapsed(), Clock::dt(), &mut self.rng);
+        for (direction, turning) in arrivals {
+            self.request(QueuedCar::new(direction, Some(turning), self.clock.tick()));
         }
+    }
 
-        cars
+    /// Ajoute une demande à la file de son approche, ou la compte comme perdue
+    /// si la file est pleine.
+    fn request(&mut self, queued: QueuedCar) {
+        if self.queues[queued.direction.road()].len() >= self.config.queue_capacity {
+            self.stats.add_dropped();
+            return;
+        }
+        self.enqueue(queued);
     }
 
-    pub fn add_car_random(&mut self) {
-        match gen_range(0, 4) {
-            0 => self.add_car(Direction::North),
-            1 => self.add_car(Direction::East),
-            2 => self.add_car(Direction::South),
-            _ => self.add_car(Direction::West),
+    /// Ajoute une demande à la file de son approche, même si elle est pleine.
+    fn enqueue(&mut self, queued: QueuedCar) {
+        let road = queued.direction.road();
+        self.queues[road].push_back(queued);
+        self.serve_queue(road);
+    }
+
+    /// Fait apparaître les voitures en tête de la file de `road` tant que leur
+    /// voie est libre et que le trafic n'est pas bloqué.
+    fn serve_queue(&mut self, road: usize) {
+        if self.queues[road].is_empty() || self.traffic_jammed() {
+            return;
+        }
+        while let Some(queued) = self.queues[road].front().cloned() {
+            if self
+                .spawn(queued.direction, queued.turning, queued.model, queued.velocity)
+                .is_none()
+            {
+                break;
+            }
+            self.queues[road].pop_front();
+            self.stats.add_queue_wait(self.clock.since(queued.tick));
         }
     }
-}
 
-fn detect_close_call(car: &Car, other_cars: &[Car]) -> bool {
-    other_cars
-        .iter()
-        .any(|c| c.id != car.id && car.calc_dist(c) <= CLOSE_CALL_DISTANCE)
-}
+    /// Nombre de voitures en attente dans la file de `direction`.
+    pub fn queue_len(&self, direction: &Direction) -> usize {
+        self.queues[direction.road()].len()
+    }
 
-fn detect_collision(car: &Car, other_cars: &[Car]) -> bool {
-    other_cars
-        .iter()
-        .any(|c| c.id != car.id && car.calc_dist(c) <= COLLISION_DISTANCE)
-}
+    /// Nombre de voitures en attente, toutes approches confondues.
+    pub fn queued(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
 
-fn detect_deadlock(other_cars: &[Car], car: &mut Car) -> bool {
-    if car.turning != Turning::Left {
-        return false;
+    /// Ajoute tout de suite un véhicule d'urgence venant de `direction`, sur une
+    /// voie libre. Contrairement à `add_car`, il est ajouté même si le trafic
+    /// est bloqué, mais la demande échoue si aucune voie n'est libre.
+    pub fn add_emergency(&mut self, direction: Direction) -> Result<CarId, SpawnError> {
+        self.spawn(direction, None, Some(Model::Ambulance), None)
+            .ok_or(SpawnError::LaneOccupied)
     }
 
-    let middle_sectors = [(5, 5), (5, 6), (6, 5), (6, 6)];
-    let cars: Vec<&Car> = other_cars
-        .iter()
-        .filter(|&c| middle_sectors.contains(&(c.sector(0).get_x(), c.sector(0).get_y())))
-        .collect();
+    /// Ajoute une voiture de modèle `model`, tiré au hasard si `None`, sur la
+    /// voie `turning` de `direction` si elle est libre, ou sur une voie libre
+    /// tirée au hasard si `None`. `velocity` est limitée à la vitesse maximale
+    /// du modèle. Retourne `None` si la voie n'est pas libre.
+    fn spawn(
+        &mut self,
+        direction: Direction,
+        turning: Option<Turning>,
+        model: Option<Model>,
+        velocity: Option<f32>,
+    ) -> Option<CarId> {
+        let road = direction.road();
+        let path = match turning {
+            Some(turning) => Some(turning).filter(|t| self.roads[road].lane_available(t)),
+            None => self.roads[road].get_available_path(&mut self.rng),
+        };
+        let path = path?;
+        let model = model.unwrap_or_else(|| Model::random(&mut self.rng));
+        let tick = self.clock.tick();
+        let id = self.total_cars;
+        let mut car = Car::new(direction, path, model, id, tick, &self.config);
+        if let Some(velocity) = velocity {
+            car.vel = velocity.min(car.top_speed(&self.config));
+        }
+        self.roads[road].add_car(car);
+        self.total_cars += 1;
+        Some(id)
+    }
 
-    if car.index == 3 && car.sector_pos() > SECTOR_WIDTH - MARGIN {
-        return cars.len() >= 2;
+    /// Programme les apparitions de `scenario`, en plus de celles déjà
+    /// programmées. Les ticks sont ceux de `clock`.
+    pub fn load_scenario(&mut self, scenario: &Scenario) {
+        self.scheduled.extend(scenario.spawns.iter().cloned());
+        self.scheduled.sort_by_key(|spawn| spawn.tick);
     }
 
-    if car.index == 4 && car.sector_pos() > SECTOR_WIDTH - MARGIN {
-        let north = cars
-            .iter()
-            .filter(|c| c.direction == Direction::North)
-            .count();
-        let east = cars
-            .iter()
-            .filter(|c| c.direction == Direction::East)
-            .count();
-        let south = cars
-            .iter()
-            .filter(|c| c.direction == Direction::South)
-            .count();
-        let west = cars
+    /// `true` quand toutes les apparitions programmées ont été faites et que
+    /// plus aucune voiture n'attend dans les files.
+    pub fn scenario_finished(&self) -> bool {
+        self.scheduled.is_empty() && self.queued() == 0
+    }
+
+    /// Demande les voitures programmées jusqu'au tick courant. Comme avec
+    /// `add_car`, une voiture dont la voie n'est pas libre attend dans la file
+    /// de son approche, mais elle n'est jamais perdue : un scénario fait
+    /// apparaître toutes les voitures qu'il liste, au-delà de `queue_capacity`.
+    fn spawn_scheduled(&mut self) {
+        let now = self.clock.tick();
+        let due = self.scheduled.partition_point(|spawn| spawn.tick <= now);
+        for spawn in self.scheduled.drain(..due).collect::<Vec<_>>() {
+            self.enqueue(QueuedCar {
+                direction: spawn.direction,
+                turning: Some(spawn.turning),
+                model: Some(spawn.model),
+                velocity: spawn.velocity,
+                tick: spawn.tick,
+            });
+        }
+    }
+
+    /// Toutes les voitures sur les routes, voie de droite comprise.
+    pub fn get_all_cars(&self) -> Vec<Car> {
+        self.roads
             .iter()
-            .filter(|c| c.direction == Direction::West)
-            .count();
-        match car.direction {
-            Direction::West => {
-                if north >= 2 || east >= 2 || south >= 2 {
-                    return false;
-                }
-            }
-            Direction::South => {
-                if north >= 2 || east >= 2 || west >= 2 {
-                    return false;
-                }
-            }
-            Direction::North => {
-                if west >= 2 || east >= 2 || south >= 2 {
-                    return false;
-                }
-            }
-            Direction::East => {
-                if north >= 2 || west >= 2 || south >= 2 {
-                    return false;
-                }
-            }
+            .flat_map(|road| road.cars.iter().flatten().cloned())
+            .collect()
+    }
+
+    pub fn add_car_random(&mut self) {
+        let direction = self.random_direction();
+        self.add_car(direction);
+    }
+
+    /// Ajoute un véhicule d'urgence venant d'une direction tirée au hasard.
+    pub fn add_emergency_random(&mut self) -> Result<CarId, SpawnError> {
+        let direction = self.random_direction();
+        self.add_emergency(direction)
+    }
+
+    fn random_direction(&mut self) -> Direction {
+        match self.rng.gen_range(0..4) {
+            0 => Direction::North,
+            1 => Direction::East,
+            2 => Direction::South,
+            _ => Direction::West,
         }
-        return cars.len() >= 3;
     }
-    false
 }
 
 impl Default for State {
     fn default() -> Self {
-        Self::new()
+        Self::new(SimulationConfig::default())
     }
 }
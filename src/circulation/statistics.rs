@@ -1,15 +1,183 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::{Incident, IncidentKind, TripRecord};
 use crate::config::FPS;
 
-#[derive(PartialEq, Debug)]
+/// Largeur des classes de l'histogramme des temps de traversée, en secondes.
+const TIME_BIN: f32 = 0.05;
+/// Largeur des classes de l'histogramme des vitesses, en px/s.
+const VELOCITY_BIN: f32 = 0.5;
+/// Largeur des classes de l'histogramme des longueurs de file, en voitures.
+const QUEUE_BIN: f32 = 1.0;
+/// Largeur des classes de l'histogramme des attentes dans les files, en secondes.
+const WAIT_BIN: f32 = 1.0;
+/// Nombre de classes de largeur `bin_width` au début d'un histogramme. Les
+/// suivantes s'élargissent chacune de `1 / LINEAR_BINS`.
+const LINEAR_BINS: usize = 512;
+/// Nombre de classes d'un histogramme, la dernière comprise : elle reçoit
+/// tous les échantillons trop grands pour les autres. Avec des classes
+/// de 0.05 s, elle commence après plus de deux ans.
+const MAX_BINS: usize = 8192;
+/// Ancienneté maximale, en ticks, des trajets gardés par un instantané. Les
+/// fenêtres glissantes du HUD doivent y tenir.
+pub const TRIP_HISTORY: u64 = 10 * 60 * FPS;
+
+/// Distribution d'une mesure, tenue à jour échantillon par échantillon.
+///
+/// La moyenne et la variance sont exactes (algorithme de Welford). Les
+/// quantiles sont lus dans un histogramme : jusqu'à `LINEAR_BINS * bin_width`,
+/// ses classes ont la largeur `bin_width` et les quantiles sont précis à
+/// `bin_width` près; au-delà, les classes s'élargissent géométriquement et les
+/// quantiles sont précis à 0.2 % près. Après la dernière des `MAX_BINS`
+/// classes, ils valent le plus grand échantillon.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Distribution {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    min: f32,
+    max: f32,
+    bin_width: f32,
+    bins: Vec<u64>,
+}
+
+impl Distribution {
+    pub fn new(bin_width: f32) -> Distribution {
+        Distribution {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f32::MAX,
+            max: f32::MIN,
+            bin_width,
+            bins: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, value: f32) {
+        self.count += 1;
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        let bin = self.bin_of(value);
+        if bin >= self.bins.len() {
+            self.bins.resize(bin + 1, 0);
+        }
+        self.bins[bin] += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Moyenne des échantillons, 0 s'il n'y en a aucun.
+    pub fn mean(&self) -> f32 {
+        self.mean as f32
+    }
+
+    /// Variance de l'échantillon (division par `n - 1`).
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64) as f32
+    }
+
+    pub fn std_dev(&self) -> f32 {
+        self.variance().sqrt()
+    }
+
+    /// Plus grand échantillon, 0 s'il n'y en a aucun.
+    pub fn max(&self) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.max
+    }
+
+    /// Quantile `q` (entre 0 et 1), au centre de la classe qui le contient.
+    pub fn quantile(&self, q: f32) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        // Rang au sens du plus proche rang; la tolérance absorbe l'imprécision
+        // de `q` en f32 (0.99 * 100 ne doit pas donner le rang 100).
+        let rank = q.clamp(0.0, 1.0) as f64 * self.count as f64 - 1e-6;
+        let rank = (rank.ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.bins.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                if i == MAX_BINS - 1 {
+                    return self.max;
+                }
+                return self.bin_center(i).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Classe de `value` dans l'histogramme.
+    fn bin_of(&self, value: f32) -> usize {
+        let scaled = value.max(0.0) as f64 / self.bin_width as f64;
+        if scaled < LINEAR_BINS as f64 {
+            return scaled as usize;
+        }
+        let growth = (1.0 + 1.0 / LINEAR_BINS as f64).ln();
+        let bin = LINEAR_BINS as f64 + (scaled / LINEAR_BINS as f64).ln() / growth;
+        (bin as usize).min(MAX_BINS - 1)
+    }
+
+    /// Centre de la classe `bin`.
+    fn bin_center(&self, bin: usize) -> f32 {
+        if bin < LINEAR_BINS {
+            return (bin as f32 + 0.5) * self.bin_width;
+        }
+        let growth = 1.0 + 1.0 / LINEAR_BINS as f64;
+        let lower = LINEAR_BINS as f64 * growth.powi((bin - LINEAR_BINS) as i32);
+        (lower * (1.0 + growth) / 2.0 * self.bin_width as f64) as f32
+    }
+}
+
+/// Statistiques d'une simulation. Les vitesses sont en px/s.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
     max_vehicles: usize,
     max_velocity: f32,
     min_velocity: f32,
     max_time: f32,
     min_time: f32,
-    close_calls: u32,
-    collisions: u32,
-    average_time: f32,
+    /// Journal des incidents, dans l'ordre où ils commencent. Il grandit d'une
+    /// entrée par incident pendant toute la simulation, pour les exports; un
+    /// instantané n'en garde que la fin, voir `trimmed`.
+    incidents: Vec<Incident>,
+    /// Index du premier incident de `incidents`, retirés compris.
+    first_incident: usize,
+    /// Presque accidents et collisions retirés du journal.
+    trimmed_close_calls: u32,
+    trimmed_collisions: u32,
+    /// Temps de traversée de chaque voiture, en secondes.
+    times: Distribution,
+    /// Vitesse de chaque voiture à chaque tick.
+    velocities: Distribution,
+    reservation_rejections: u32,
+    /// Journal des trajets, qui grandit comme `incidents`.
+    trips: Vec<TripRecord>,
+    /// Trajets retirés du journal.
+    trimmed_trips: usize,
+    /// Temps perdu par chaque véhicule d'urgence, en secondes.
+    emergency_delays: Distribution,
+    /// Attente de chaque voiture dans la file de son approche, en secondes.
+    queue_waits: Distribution,
+    /// Longueur de la file de chaque approche, à chaque tick.
+    queue_lengths: Distribution,
+    /// Demandes perdues parce que la file de leur approche était pleine.
+    dropped: u32,
 }
 
 impl Statistics {
@@ -20,9 +188,19 @@ impl Statistics {
             min_velocity: 0.0,
             max_time: 0.0,
             min_time: 0.0,
-            close_calls: 0,
-            collisions: 0,
-            average_time: 0.0, // Initialisation de average_time à 0.0
+            incidents: Vec::new(),
+            first_incident: 0,
+            trimmed_close_calls: 0,
+            trimmed_collisions: 0,
+            times: Distribution::new(TIME_BIN),
+            velocities: Distribution::new(VELOCITY_BIN),
+            reservation_rejections: 0,
+            trips: Vec::new(),
+            trimmed_trips: 0,
+            emergency_delays: Distribution::new(TIME_BIN),
+            queue_waits: Distribution::new(WAIT_BIN),
+            queue_lengths: Distribution::new(QUEUE_BIN),
+            dropped: 0,
         }
     }
 
@@ -34,6 +212,7 @@ impl Statistics {
     }
 
     pub fn set_velocity(&mut self, velocity: f32) {
+        self.velocities.add(velocity);
         self.set_min_velocity(velocity);
         self.set_max_velocity(velocity);
     }
@@ -55,6 +234,7 @@ impl Statistics {
     }
 
     pub fn set_time(&mut self, time: f32) {
+        self.times.add(time);
         self.set_min_time(time);
         self.set_max_time(time);
     }
@@ -75,17 +255,42 @@ impl Statistics {
         }
     }
 
-    pub fn add_time(&mut self, time: f32) {
-        let current_count = self.close_calls + self.collisions;
-        self.average_time = (self.average_time * current_count as f32 + time) / (current_count + 1) as f32;
+    /// Ajoute un incident au journal et retourne son index.
+    pub fn add_incident(&mut self, incident: Incident) -> usize {
+        self.incidents.push(incident);
+        self.first_incident + self.incidents.len() - 1
+    }
+
+    pub(crate) fn incident_mut(&mut self, index: usize) -> &mut Incident {
+        &mut self.incidents[index - self.first_incident]
     }
 
-    pub fn set_close_calls(&mut self) {
-        self.close_calls += 1;
+    /// `true` si l'incident d'index `index` est encore dans le journal.
+    pub(crate) fn has_incident(&self, index: usize) -> bool {
+        (self.first_incident..self.first_incident + self.incidents.len()).contains(&index)
     }
 
-    pub fn set_collisions(&mut self) {
-        self.collisions += 1;
+    pub fn set_reservation_rejections(&mut self) {
+        self.reservation_rejections += 1;
+    }
+
+    pub fn add_trip(&mut self, trip: TripRecord) {
+        if trip.model.is_emergency() {
+            self.emergency_delays.add(trip.delay);
+        }
+        self.trips.push(trip);
+    }
+
+    pub fn add_queue_wait(&mut self, wait: f32) {
+        self.queue_waits.add(wait);
+    }
+
+    pub fn add_queue_length(&mut self, length: usize) {
+        self.queue_lengths.add(length as f32);
+    }
+
+    pub fn add_dropped(&mut self) {
+        self.dropped += 1;
     }
 
     // Getters
@@ -109,16 +314,146 @@ impl Statistics {
         self.min_time
     }
 
+    /// Temps de traversée moyen, en secondes.
     pub fn average_time(&self) -> f32 {
-        (self.min_time + self.max_time) / 2.0
+        self.times.mean()
+    }
+
+    /// Distribution des temps de traversée, en secondes.
+    pub fn times(&self) -> &Distribution {
+        &self.times
     }
 
+    /// Distribution des vitesses, un échantillon par voiture et par tick.
+    pub fn velocities(&self) -> &Distribution {
+        &self.velocities
+    }
+
+    /// Nombre de presque accidents : paires de voitures dont les emprises sont
+    /// passées sous `close_call_gap` sans entrer en collision.
     pub fn close_calls(&self) -> u32 {
-        (self.close_calls / 2) / FPS as u32
+        self.count_incidents(IncidentKind::CloseCall)
     }
 
+    /// Nombre de paires de voitures dont les emprises se sont chevauchées, ou
+    /// sont passées sous `collision_gap`.
     pub fn collisions(&self) -> u32 {
-        (self.collisions / 2) / FPS as u32
+        self.count_incidents(IncidentKind::Collision)
+    }
+
+    fn count_incidents(&self, kind: IncidentKind) -> u32 {
+        let trimmed = match kind {
+            IncidentKind::CloseCall => self.trimmed_close_calls,
+            IncidentKind::Collision => self.trimmed_collisions,
+        };
+        trimmed + self.incidents.iter().filter(|i| i.kind == kind).count() as u32
+    }
+
+    /// Journal des incidents. Après `State::restore`, il commence au plus
+    /// ancien incident en cours au moment de l'instantané.
+    pub fn incidents(&self) -> &[Incident] {
+        &self.incidents
+    }
+
+    /// Demandes refusées par le gestionnaire de réservations.
+    pub fn reservation_rejections(&self) -> u32 {
+        self.reservation_rejections
+    }
+
+    /// Distribution du temps perdu par les véhicules d'urgence par rapport à un
+    /// trajet à vitesse maximale, en secondes.
+    pub fn emergency_delays(&self) -> &Distribution {
+        &self.emergency_delays
+    }
+
+    /// Distribution de l'attente hors de la fenêtre, en secondes, une par
+    /// voiture sortie de sa file.
+    pub fn queue_waits(&self) -> &Distribution {
+        &self.queue_waits
+    }
+
+    /// Distribution des longueurs de file, un échantillon par approche et par tick.
+    pub fn queue_lengths(&self) -> &Distribution {
+        &self.queue_lengths
+    }
+
+    /// Demandes perdues, voir `SimulationConfig::queue_capacity`.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Trajets des voitures sorties de la fenêtre, dans l'ordre de sortie. Après
+    /// `State::restore`, seuls ceux des `TRIP_HISTORY` ticks qui précèdent
+    /// l'instantané sont conservés.
+    pub fn trips(&self) -> &[TripRecord] {
+        &self.trips
+    }
+
+    /// Nombre de voitures sorties de la fenêtre, trajets retirés compris.
+    pub fn exited(&self) -> usize {
+        self.trimmed_trips + self.trips.len()
+    }
+
+    /// Copie pour un instantané, sans les trajets antérieurs à `TRIP_HISTORY`
+    /// ticks avant `now` ni les incidents antérieurs à `oldest_active`, le plus
+    /// ancien incident en cours. Les compteurs restent exacts.
+    pub(crate) fn trimmed(&self, now: u64, oldest_active: Option<usize>) -> Statistics {
+        let mut stats = self.clone();
+        let start = now.saturating_sub(TRIP_HISTORY);
+        let old_trips = stats.trips.partition_point(|t| t.exit_tick < start);
+        stats.trips.drain(..old_trips);
+        stats.trimmed_trips += old_trips;
+
+        let end = stats.first_incident + stats.incidents.len();
+        let first = oldest_active.unwrap_or(end);
+        for incident in stats.incidents.drain(..first - stats.first_incident) {
+            match incident.kind {
+                IncidentKind::CloseCall => stats.trimmed_close_calls += 1,
+                IncidentKind::Collision => stats.trimmed_collisions += 1,
+            }
+        }
+        stats.first_incident = first;
+        stats
+    }
+
+    /// Trajets terminés pendant les `window` ticks qui précèdent `now`.
+    fn recent_trips(&self, now: u64, window: u64) -> &[TripRecord] {
+        let start = now.saturating_sub(window);
+        let first = self.trips.partition_point(|t| t.exit_tick < start);
+        let end = self.trips.partition_point(|t| t.exit_tick <= now);
+        &self.trips[first..end.max(first)]
+    }
+
+    /// Débit de sortie pendant les `window` ticks qui précèdent `now`, en
+    /// voitures par minute. Avant `window` ticks, la moyenne porte sur le
+    /// temps écoulé.
+    pub fn throughput(&self, now: u64, window: u64) -> f32 {
+        let minutes = window.min(now).max(1) as f32 / (60 * FPS) as f32;
+        self.recent_trips(now, window).len() as f32 / minutes
+    }
+
+    /// Temps de traversée moyen des voitures sorties pendant les `window`
+    /// ticks qui précèdent `now`, en secondes. 0 si aucune n'est sortie.
+    pub fn recent_mean_time(&self, now: u64, window: u64) -> f32 {
+        let trips = self.recent_trips(now, window);
+        if trips.is_empty() {
+            return 0.0;
+        }
+        trips.iter().map(TripRecord::time).sum::<f32>() / trips.len() as f32
+    }
+
+    /// Nombre de sorties dans chacune des `bins` dernières tranches complètes
+    /// de `bin` ticks avant `now`, la plus ancienne en premier.
+    pub fn exit_history(&self, now: u64, bin: u64, bins: usize) -> Vec<u32> {
+        let current = now / bin;
+        let mut history = vec![0; bins];
+        for trip in self.recent_trips(now, (bins as u64 + 1) * bin) {
+            let age = current - trip.exit_tick / bin;
+            if (1..=bins as u64).contains(&age) {
+                history[bins - age as usize] += 1;
+            }
+        }
+        history
     }
 }
 
@@ -127,3 +462,68 @@ impl Default for Statistics {
         Self::new()
     }
 }
+
+impl Statistics {
+    /// Statistiques formatées pour l'affichage, sous forme de paires `(libellé, valeur)`.
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Max Vehicles", format!("{} cars", self.max_vehicles())),
+            ("Max Velocity", format!("{:.1} px/s", self.max_velocity())),
+            ("Min Velocity", format!("{:.1} px/s", self.min_velocity())),
+            ("Max Time", format!("{:.1} s", self.max_time())),
+            ("Min Time", format!("{:.1} s", self.min_time())),
+            ("Close Calls", self.close_calls().to_string()),
+            ("Collisions", self.collisions().to_string()),
+            ("Average Time", format!("{:.1} s", self.average_time())),
+            ("Time Std Dev", format!("{:.1} s", self.times.std_dev())),
+            ("Time p50", format!("{:.1} s", self.times.quantile(0.50))),
+            ("Time p90", format!("{:.1} s", self.times.quantile(0.90))),
+            ("Time p99", format!("{:.1} s", self.times.quantile(0.99))),
+            (
+                "Avg Velocity",
+                format!("{:.1} px/s", self.velocities.mean()),
+            ),
+            (
+                "Velocity p50",
+                format!("{:.1} px/s", self.velocities.quantile(0.50)),
+            ),
+            (
+                "Velocity p90",
+                format!("{:.1} px/s", self.velocities.quantile(0.90)),
+            ),
+            (
+                "Velocity p99",
+                format!("{:.1} px/s", self.velocities.quantile(0.99)),
+            ),
+            ("Rejections", self.reservation_rejections().to_string()),
+            ("Emergencies", self.emergency_delays.count().to_string()),
+            (
+                "Emergency Delay",
+                format!("{:.1} s", self.emergency_delays.mean()),
+            ),
+            (
+                "Emergency Max",
+                format!("{:.1} s", self.emergency_delays.max()),
+            ),
+            ("Queue Wait", format!("{:.1} s", self.queue_waits.mean())),
+            ("Queue Wait Max", format!("{:.1} s", self.queue_waits.max())),
+            (
+                "Avg Queue",
+                format!("{:.1} cars", self.queue_lengths.mean()),
+            ),
+            ("Max Queue", format!("{:.0} cars", self.queue_lengths.max())),
+            ("Dropped", self.dropped.to_string()),
+        ]
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .summary()
+            .into_iter()
+            .map(|(label, value)| format!("{label}: {value}"))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::controller::{
+    follow_lane, ControllerSnapshot, IntersectionController, TickContext,
+};
+use crate::circulation::traffic_light::Movement;
+use crate::circulation::{Car, Direction, Statistics};
+
+/// Ordre fixe utilisé quand quatre voitures arrivent au même tick et que la
+/// priorité à droite ne désigne personne.
+const FALLBACK_ORDER: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+/// Arrêt obligatoire sur les quatre approches.
+///
+/// Chaque voiture s'arrête complètement à la ligne, dans le secteur d'index 2
+/// de son `Path`. Les voitures arrêtées passent ensuite dans leur ordre
+/// d'arrivée, la priorité à droite départageant les arrivées simultanées. Une
+/// voiture passe dès que son `Path` ne croise ni celui d'une voiture engagée,
+/// ni celui d'une voiture arrivée avant elle.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct StopSign {
+    /// Tick de l'arrêt à la ligne de chaque voiture en attente.
+    arrivals: BTreeMap<usize, u64>,
+    /// Voitures autorisées à traverser, jusqu'à leur sortie de l'intersection.
+    released: BTreeSet<usize>,
+}
+
+impl StopSign {
+    pub fn new() -> StopSign {
+        StopSign::default()
+    }
+
+    /// `true` si `car` a été autorisée à traverser.
+    pub fn is_released(&self, car: &Car) -> bool {
+        self.released.contains(&car.id)
+    }
+
+    fn at_line(car: &Car, ctx: &TickContext) -> bool {
+        car.vel == 0.0
+            && car.index <= car.path.entry_index()
+            && !car.in_intersection()
+            && car.distance_to(car.path.entry_index() - 1) <= ctx.config.margin
+    }
+
+    /// Voitures en attente parmi `cars`, dans l'ordre où elles doivent passer.
+    fn queue<'a>(&self, cars: &'a [Car]) -> Vec<&'a Car> {
+        let mut waiting: Vec<(u64, &Car)> = cars
+            .iter()
+            .filter_map(|c| self.arrivals.get(&c.id).map(|&tick| (tick, c)))
+            .collect();
+        waiting.sort_by_key(|&(tick, c)| (tick, c.id));
+
+        let mut queue = Vec::with_capacity(waiting.len());
+        while !waiting.is_empty() {
+            let tick = waiting[0].0;
+            let tied: Vec<&Car> = waiting
+                .iter()
+                .take_while(|(t, _)| *t == tick)
+                .map(|&(_, c)| c)
+                .collect();
+            // Priorité à droite : passe d'abord celle qui n'a personne à sa droite.
+            let next = tied
+                .iter()
+                .find(|c| !tied.iter().any(|o| o.direction == c.direction.on_right()))
+                .or_else(|| {
+                    FALLBACK_ORDER
+                        .iter()
+                        .find_map(|d| tied.iter().find(|c| c.direction == *d))
+                })
+                .map(|c| c.id)
+                .unwrap_or(tied[0].id);
+            let i = waiting.iter().position(|(_, c)| c.id == next).unwrap();
+            queue.push(waiting.remove(i).1);
+        }
+        queue
+    }
+}
+
+impl IntersectionController for StopSign {
+    fn name(&self) -> &'static str {
+        "stop_sign"
+    }
+
+    fn begin_tick(&mut self, cars: &[Car], _ctx: &TickContext, _stats: &mut Statistics) {
+        self.released.retain(|id| {
+            cars.iter()
+                .any(|c| c.id == *id && !c.cleared_intersection())
+        });
+
+        // Voitures engagées, puis voitures déjà passées en revue dans la file :
+        // une voiture ne peut croiser aucune d'entre elles.
+        let mut ahead: Vec<&Car> = cars.iter().filter(|c| self.is_released(c)).collect();
+        for car in self.queue(cars) {
+            if ahead.iter().all(|other| !car.path.crosses(&other.path)) {
+                self.arrivals.remove(&car.id);
+                self.released.insert(car.id);
+            }
+            ahead.push(car);
+        }
+    }
+
+    fn drive(&mut self, car: &mut Car, cars: &[Car], ctx: &TickContext) {
+        let unconflicted = Movement::of(car).is_unconflicted();
+        if StopSign::at_line(car, ctx) && !self.is_released(car) {
+            self.arrivals.entry(car.id).or_insert(ctx.clock.tick());
+        }
+        // Une voiture qui ne croise personne repart dès qu'elle s'est arrêtée.
+        let cleared =
+            self.is_released(car) || (unconflicted && self.arrivals.contains_key(&car.id));
+        let hold = !cleared && car.index <= car.path.entry_index() && !car.in_intersection();
+        follow_lane(car, cars, ctx.config, hold);
+        if car.index > car.path.entry_index() {
+            self.arrivals.remove(&car.id);
+        }
+    }
+
+    fn preempt(&mut self, car: &Car) {
+        // La voiture repassera en tête de file, après le véhicule d'urgence.
+        if !car.in_intersection() && self.released.remove(&car.id) {
+            self.arrivals.insert(car.id, 0);
+        }
+    }
+
+    fn snapshot(&self) -> Option<ControllerSnapshot> {
+        Some(ControllerSnapshot::StopSign(self.clone()))
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::{Car, CarId, Direction, Incident, Model, Turning};
+use crate::config::{SimulationConfig, FPS};
+
+/// Première ligne d'une trace : de quoi reconstruire les voitures à l'échelle
+/// de la simulation enregistrée.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceHeader {
+    pub seed: u64,
+    pub config: SimulationConfig,
+}
+
+/// Apparition d'une voiture.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CarSpawn {
+    pub id: CarId,
+    pub direction: Direction,
+    pub turning: Turning,
+    pub model: Model,
+}
+
+/// Position d'une voiture à la fin d'un tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CarState {
+    pub id: CarId,
+    pub x: f32,
+    pub y: f32,
+    pub vel: f32,
+    pub index: usize,
+}
+
+/// Une ligne de la trace par tick de `Clock`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceFrame {
+    pub tick: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawns: Vec<CarSpawn>,
+    pub cars: Vec<CarState>,
+    /// Incidents commencés, aggravés ou terminés pendant ce tick.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incidents: Vec<Incident>,
+}
+
+/// Écrit une trace au format JSON lines : l'en-tête, puis une ligne par tick.
+#[derive(Debug)]
+pub struct TraceWriter {
+    out: BufWriter<File>,
+    /// Nombre de voitures et d'incidents déjà annoncés.
+    spawned: usize,
+    incidents: usize,
+    /// Incidents en cours, par index dans `Statistics::incidents`, tels
+    /// qu'ils ont été écrits pour la dernière fois.
+    open: BTreeMap<usize, Incident>,
+    /// Première erreur d'écriture, rendue par `finish`.
+    error: Option<io::Error>,
+}
+
+impl TraceWriter {
+    pub fn create(path: impl AsRef<Path>, header: &TraceHeader) -> io::Result<TraceWriter> {
+        let mut out = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut out, header)?;
+        writeln!(out)?;
+        Ok(TraceWriter {
+            out,
+            spawned: 0,
+            incidents: 0,
+            open: BTreeMap::new(),
+            error: None,
+        })
+    }
+
+    /// Écrit la ligne du tick `tick`. Les voitures dont l'id n'a pas encore été
+    /// vu sont annoncées dans `spawns`; un incident est réécrit chaque fois
+    /// qu'il change.
+    pub fn write_frame(&mut self, tick: u64, cars: &[Car], incidents: &[Incident]) {
+        if self.error.is_some() {
+            return;
+        }
+        let spawns = cars
+            .iter()
+            .filter(|c| c.id >= self.spawned)
+            .map(|c| CarSpawn {
+                id: c.id,
+                direction: c.direction.clone(),
+                turning: c.turning.clone(),
+                model: c.model.clone(),
+            })
+            .collect();
+        let frame = TraceFrame {
+            tick,
+            spawns,
+            cars: cars
+                .iter()
+                .map(|c| CarState {
+                    id: c.id,
+                    x: c.x,
+                    y: c.y,
+                    vel: c.vel,
+                    index: c.index,
+                })
+                .collect(),
+            incidents: self.changed_incidents(incidents),
+        };
+        self.spawned = cars.iter().map(|c| c.id + 1).fold(self.spawned, usize::max);
+
+        let written = serde_json::to_writer(&mut self.out, &frame)
+            .map_err(io::Error::from)
+            .and_then(|()| writeln!(self.out));
+        if let Err(err) = written {
+            self.error = Some(err);
+        }
+    }
+
+    /// Incidents nouveaux ou modifiés depuis la ligne précédente.
+    fn changed_incidents(&mut self, incidents: &[Incident]) -> Vec<Incident> {
+        let mut changed = Vec::new();
+        self.open.retain(|&index, written| {
+            let incident = &incidents[index];
+            if incident != written {
+                changed.push(incident.clone());
+                *written = incident.clone();
+            }
+            incident.end_tick.is_none()
+        });
+        for (index, incident) in incidents.iter().enumerate().skip(self.incidents) {
+            changed.push(incident.clone());
+            if incident.end_tick.is_none() {
+                self.open.insert(index, incident.clone());
+            }
+        }
+        self.incidents = incidents.len();
+        changed
+    }
+
+    /// Vide le tampon et rend la première erreur d'écriture.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.out.flush(),
+        }
+    }
+}
+
+/// Trace chargée en mémoire, pour la rejouer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub header: TraceHeader,
+    pub frames: Vec<TraceFrame>,
+    /// Apparition de chaque voiture, avec le tick où elle a eu lieu.
+    spawns: BTreeMap<CarId, (CarSpawn, u64)>,
+    /// Dernier état connu de chaque incident, dans l'ordre où ils commencent.
+    pub incidents: Vec<Incident>,
+}
+
+impl Trace {
+    pub fn from_json_lines(content: &str) -> Result<Trace, TraceError> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let parse_error =
+            |i: usize, e: serde_json::Error| TraceError::Parse(format!("line {}: {e}", i + 1));
+        let (i, first) = lines
+            .next()
+            .ok_or_else(|| TraceError::Parse("empty trace".to_owned()))?;
+        let header: TraceHeader = serde_json::from_str(first).map_err(|e| parse_error(i, e))?;
+        let frames = lines
+            .map(|(i, line)| serde_json::from_str(line).map_err(|e| parse_error(i, e)))
+            .collect::<Result<Vec<TraceFrame>, _>>()?;
+        let spawns = frames
+            .iter()
+            .flat_map(|f| f.spawns.iter().map(|s| (s.id, (s.clone(), f.tick))))
+            .collect();
+        let mut incidents: Vec<Incident> = Vec::new();
+        for incident in frames.iter().flat_map(|f| &f.incidents) {
+            let same =
+                |i: &Incident| (i.cars, i.start_tick) == (incident.cars, incident.start_tick);
+            match incidents.iter_mut().rev().find(|i| same(i)) {
+                Some(known) => *known = incident.clone(),
+                None => incidents.push(incident.clone()),
+            }
+        }
+        Ok(Trace {
+            header,
+            frames,
+            spawns,
+            incidents,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Trace, TraceError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| TraceError::Io(format!("{}: {e}", path.display())))?;
+        Trace::from_json_lines(&content)
+    }
+
+    /// Voitures de la ligne `frame`, prêtes à être dessinées.
+    pub fn cars(&self, frame: usize) -> Vec<Car> {
+        let Some(frame) = self.frames.get(frame) else {
+            return Vec::new();
+        };
+        frame
+            .cars
+            .iter()
+            .filter_map(|state| {
+                let (spawn, tick) = self.spawns.get(&state.id)?;
+                let mut car = Car::new(
+                    spawn.direction.clone(),
+                    spawn.turning.clone(),
+                    spawn.model.clone(),
+                    spawn.id,
+                    *tick,
+                    &self.header.config,
+                );
+                car.index = state.index.min(car.path.sectors.len() - 1);
+                car.moving = car.path.sectors[car.index].moving.clone();
+                (car.x, car.y, car.vel) = (state.x, state.y, state.vel);
+                Some(car)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceError {
+    Io(String),
+    Parse(String),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Io(msg) => write!(f, "cannot read trace: {msg}"),
+            TraceError::Parse(msg) => write!(f, "invalid trace: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+/// Vitesses de lecture extrêmes, en multiples du temps réel.
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 16.0;
+
+/// Lecture d'une trace : pause, déplacement et vitesse variable.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub trace: Trace,
+    /// Ligne courante, fractionnaire entre deux images.
+    position: f32,
+    pub paused: bool,
+    /// Multiple du temps réel.
+    speed: f32,
+}
+
+impl Replay {
+    pub fn new(trace: Trace) -> Replay {
+        Replay {
+            trace,
+            position: 0.0,
+            paused: false,
+            speed: 1.0,
+        }
+    }
+
+    /// Index de la ligne courante.
+    pub fn frame(&self) -> usize {
+        self.position as usize
+    }
+
+    /// Tick de la ligne courante.
+    pub fn tick(&self) -> u64 {
+        self.trace.frames.get(self.frame()).map_or(0, |f| f.tick)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame() + 1 >= self.trace.frames.len()
+    }
+
+    /// Avance de `seconds` secondes de temps réel, à la vitesse de lecture.
+    pub fn advance(&mut self, seconds: f32) {
+        if !self.paused {
+            self.seek_frames(seconds * self.speed * FPS as f32);
+        }
+    }
+
+    /// Avance, ou recule si `seconds` est négatif, de `seconds` secondes simulées.
+    pub fn seek(&mut self, seconds: f32) {
+        self.seek_frames(seconds * FPS as f32);
+    }
+
+    /// Avance, ou recule, de `frames` lignes.
+    pub fn seek_frames(&mut self, frames: f32) {
+        let last = self.trace.frames.len().saturating_sub(1) as f32;
+        self.position = (self.position + frames).clamp(0.0, last);
+    }
+
+    /// Revient au début de la trace.
+    pub fn restart(&mut self) {
+        self.position = 0.0;
+    }
+
+    /// Multiplie la vitesse de lecture par `factor`.
+    pub fn scale_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Voitures de la ligne courante.
+    pub fn cars(&self) -> Vec<Car> {
+        self.trace.cars(self.frame())
+    }
+
+    /// Incidents en cours à la ligne courante.
+    pub fn active_incidents(&self) -> impl Iterator<Item = &Incident> {
+        let tick = self.tick();
+        self.trace
+            .incidents
+            .iter()
+            .filter(move |i| i.start_tick <= tick && i.end_tick.is_none_or(|end| tick < end))
+    }
+
+    /// Dernier incident commencé avant la ligne courante.
+    pub fn last_incident(&self) -> Option<&Incident> {
+        let tick = self.tick();
+        self.trace
+            .incidents
+            .iter()
+            .rev()
+            .find(|i| i.start_tick <= tick)
+    }
+}
@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::controller::{
+    follow_lane, ControllerSnapshot, IntersectionController, TickContext,
+};
+use crate::circulation::{Car, Direction, Path, Statistics, Turning};
+use crate::config::FPS;
+
+/// Un mouvement : une approche et la voie qu'elle emprunte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Movement {
+    pub direction: Direction,
+    pub turning: Turning,
+}
+
+impl Movement {
+    pub fn new(direction: Direction, turning: Turning) -> Movement {
+        Movement { direction, turning }
+    }
+
+    pub fn of(car: &Car) -> Movement {
+        Movement::new(car.direction.clone(), car.turning.clone())
+    }
+
+    pub fn path(&self) -> Path {
+        Path::new(&self.direction, &self.turning)
+    }
+
+    /// Tous les mouvements possibles dans l'intersection.
+    pub fn all() -> Vec<Movement> {
+        let directions = [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ];
+        let turnings = [Turning::Left, Turning::Straight, Turning::Right];
+        directions
+            .iter()
+            .flat_map(|d| turnings.iter().map(|t| Movement::new(d.clone(), t.clone())))
+            .collect()
+    }
+
+    /// `true` si le mouvement ne croise aucun autre mouvement. C'est le cas des
+    /// virages à droite, qui n'ont donc jamais besoin de feu.
+    pub fn is_unconflicted(&self) -> bool {
+        let path = self.path();
+        Movement::all()
+            .iter()
+            .filter(|m| m.direction != self.direction)
+            .all(|m| !path.crosses(&m.path()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Green,
+    Red,
+}
+
+/// Une phase du cycle : les mouvements qui ont le vert en même temps.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignalPhase {
+    pub green: Vec<Movement>,
+    /// Durée du vert, en secondes. Sans valeur, `SignalPlan::green` est utilisé.
+    #[serde(default)]
+    pub duration: Option<f32>,
+}
+
+impl SignalPhase {
+    fn new(green: Vec<Movement>) -> SignalPhase {
+        SignalPhase {
+            green,
+            duration: None,
+        }
+    }
+}
+
+/// Plans de phases prédéfinis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhasePreset {
+    /// Une phase par approche, tous virages confondus.
+    #[default]
+    Split,
+    /// Tout droit des approches opposées ensemble, puis chaque virage à gauche
+    /// protégé avec le tout droit de son approche.
+    ProtectedLeft,
+}
+
+/// Plan de feux, lu depuis la section `signals` de la configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignalPlan {
+    pub preset: PhasePreset,
+    /// Phases explicites. Si la liste n'est pas vide, elle remplace `preset`.
+    pub phases: Vec<SignalPhase>,
+    /// Durée du vert par défaut, en secondes.
+    pub green: f32,
+    /// Rouge intégral entre deux phases, en secondes. Il se prolonge tant que
+    /// l'intersection n'est pas dégagée.
+    pub all_red: f32,
+    /// Feux actionnés : une phase sans voiture en attente est sautée, et le
+    /// vert s'arrête dès qu'il n'y a plus de demande après `min_green`.
+    pub actuated: bool,
+    /// Durée minimale du vert en mode actionné, en secondes.
+    pub min_green: f32,
+}
+
+impl Default for SignalPlan {
+    fn default() -> Self {
+        SignalPlan {
+            preset: PhasePreset::default(),
+            phases: Vec::new(),
+            green: 6.0,
+            all_red: 1.0,
+            actuated: false,
+            min_green: 2.0,
+        }
+    }
+}
+
+impl SignalPlan {
+    /// Phases du cycle, dans l'ordre.
+    pub fn phases(&self) -> Vec<SignalPhase> {
+        if !self.phases.is_empty() {
+            return self.phases.clone();
+        }
+        use Direction::*;
+        let all_turns = |d: Direction| {
+            vec![
+                Movement::new(d.clone(), Turning::Left),
+                Movement::new(d.clone(), Turning::Straight),
+                Movement::new(d, Turning::Right),
+            ]
+        };
+        let through = |a: Direction, b: Direction| {
+            vec![
+                Movement::new(a, Turning::Straight),
+                Movement::new(b, Turning::Straight),
+            ]
+        };
+        let left = |d: Direction| {
+            vec![
+                Movement::new(d.clone(), Turning::Left),
+                Movement::new(d, Turning::Straight),
+            ]
+        };
+        let phases = match self.preset {
+            PhasePreset::Split => vec![
+                all_turns(North),
+                all_turns(East),
+                all_turns(South),
+                all_turns(West),
+            ],
+            PhasePreset::ProtectedLeft => vec![
+                through(North, South),
+                left(North),
+                left(South),
+                through(East, West),
+                left(East),
+                left(West),
+            ],
+        };
+        phases.into_iter().map(SignalPhase::new).collect()
+    }
+
+    /// Vérifie qu'aucune phase ne donne le vert à deux mouvements qui se croisent,
+    /// et que chaque mouvement qui en croise un autre a le vert dans au moins une
+    /// phase de durée non nulle : sinon ses voitures attendraient indéfiniment.
+    pub fn validate(&self) -> Result<(), String> {
+        let phases = self.phases();
+        if phases.iter().all(|p| p.green.is_empty()) {
+            return Err("signal plan has no green movement".to_owned());
+        }
+        if let Some(movement) = Movement::all().into_iter().find(|m| {
+            !m.is_unconflicted()
+                && !phases
+                    .iter()
+                    .any(|p| p.duration.unwrap_or(self.green) > 0.0 && p.green.contains(m))
+        }) {
+            return Err(format!(
+                "signal plan never gives green to {:?} {:?}",
+                movement.direction, movement.turning
+            ));
+        }
+        for (i, phase) in phases.iter().enumerate() {
+            for (j, a) in phase.green.iter().enumerate() {
+                if let Some(b) = phase.green[j + 1..]
+                    .iter()
+                    .find(|b| b.direction != a.direction && a.path().crosses(&b.path()))
+                {
+                    return Err(format!(
+                        "phase {} gives green to crossing movements {:?} {:?} and {:?} {:?}",
+                        i + 1,
+                        a.direction,
+                        a.turning,
+                        b.direction,
+                        b.turning
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ticks(seconds: f32) -> u64 {
+    (seconds * FPS as f32).round() as u64
+}
+
+/// Feux tricolores à cycle fixe ou actionnés, sur les quatre routes.
+///
+/// Les voitures dont le feu est rouge s'arrêtent dans le secteur d'index 2 de
+/// leur `Path`, juste avant l'intersection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficLights {
+    plan: SignalPlan,
+    phases: Vec<SignalPhase>,
+    current: usize,
+    /// Tick de début de la phase courante, ou du rouge intégral.
+    since: u64,
+    clearing: bool,
+}
+
+impl TrafficLights {
+    pub fn new(plan: SignalPlan) -> TrafficLights {
+        TrafficLights {
+            phases: plan.phases(),
+            plan,
+            current: 0,
+            since: 0,
+            clearing: false,
+        }
+    }
+
+    /// Index de la phase courante dans `SignalPlan::phases`.
+    pub fn current_phase(&self) -> usize {
+        self.current
+    }
+
+    pub fn is_clearing(&self) -> bool {
+        self.clearing
+    }
+
+    fn has_demand(phase: &SignalPhase, cars: &[Car]) -> bool {
+        cars.iter().any(|c| {
+            phase.green.contains(&Movement::of(c))
+                && c.index <= c.path.entry_index()
+                && !c.in_intersection()
+        })
+    }
+
+    fn next_phase(&self, cars: &[Car]) -> usize {
+        let n = self.phases.len();
+        let next = (self.current + 1) % n;
+        if !self.plan.actuated {
+            return next;
+        }
+        (0..n)
+            .map(|i| (next + i) % n)
+            .find(|&i| TrafficLights::has_demand(&self.phases[i], cars))
+            .unwrap_or(next)
+    }
+
+    pub fn signal_for(&self, movement: &Movement) -> Signal {
+        if movement.is_unconflicted()
+            || (!self.clearing && self.phases[self.current].green.contains(movement))
+        {
+            Signal::Green
+        } else {
+            Signal::Red
+        }
+    }
+}
+
+impl IntersectionController for TrafficLights {
+    fn name(&self) -> &'static str {
+        "traffic_lights"
+    }
+
+    fn begin_tick(&mut self, cars: &[Car], ctx: &TickContext, _stats: &mut Statistics) {
+        let now = ctx.clock.tick();
+        let elapsed = now - self.since;
+
+        if self.clearing {
+            let occupied = cars
+                .iter()
+                .any(|c| c.in_intersection() && !Movement::of(c).is_unconflicted());
+            if elapsed >= ticks(self.plan.all_red) && !occupied {
+                self.current = self.next_phase(cars);
+                self.clearing = false;
+                self.since = now;
+            }
+            return;
+        }
+
+        let phase = &self.phases[self.current];
+        let max_green = ticks(phase.duration.unwrap_or(self.plan.green));
+        let gap_out = self.plan.actuated
+            && elapsed >= ticks(self.plan.min_green)
+            && !TrafficLights::has_demand(phase, cars);
+        if elapsed >= max_green || gap_out {
+            self.clearing = true;
+            self.since = now;
+        }
+    }
+
+    fn drive(&mut self, car: &mut Car, cars: &[Car], ctx: &TickContext) {
+        let hold = car.index <= car.path.entry_index()
+            && !car.in_intersection()
+            && self.signal_for(&Movement::of(car)) == Signal::Red;
+        follow_lane(car, cars, ctx.config, hold);
+    }
+
+    fn signal(&self, direction: &Direction, turning: &Turning) -> Option<Signal> {
+        Some(self.signal_for(&Movement::new(direction.clone(), turning.clone())))
+    }
+
+    fn snapshot(&self) -> Option<ControllerSnapshot> {
+        Some(ControllerSnapshot::TrafficLights(self.clone()))
+    }
+}
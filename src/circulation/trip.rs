@@ -0,0 +1,160 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circulation::{Clock, Direction, Model, Turning};
+
+/// Trajet d'une voiture, de sa création à sa sortie de la fenêtre.
+///
+/// Les ticks sont ceux de `Clock`; les vitesses sont en px/s, comme dans `Statistics`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TripRecord {
+    pub id: usize,
+    pub direction: Direction,
+    pub turning: Turning,
+    pub model: Model,
+    pub spawn_tick: u64,
+    /// Premier tick passé dans l'intersection.
+    pub enter_tick: Option<u64>,
+    pub exit_tick: u64,
+    pub min_vel: f32,
+    pub max_vel: f32,
+    /// Nombre d'arrêts complets.
+    pub stops: u32,
+    /// Nombre de fois où l'emprise d'une autre voiture s'est approchée à moins
+    /// de `close_call_gap`.
+    pub close_calls: u32,
+    /// Temps perdu par rapport à un trajet à vitesse maximale, en secondes.
+    pub delay: f32,
+}
+
+impl TripRecord {
+    /// Durée du trajet, en secondes.
+    pub fn time(&self) -> f32 {
+        (self.exit_tick - self.spawn_tick) as f32 * Clock::dt()
+    }
+
+    /// Attente avant d'entrer dans l'intersection, en secondes.
+    pub fn approach_time(&self) -> Option<f32> {
+        self.enter_tick
+            .map(|tick| (tick - self.spawn_tick) as f32 * Clock::dt())
+    }
+}
+
+/// Mesures accumulées par une voiture pendant son trajet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TripTracker {
+    enter_tick: Option<u64>,
+    min_vel: Option<f32>,
+    max_vel: f32,
+    stops: u32,
+    close_calls: u32,
+    delay: f32,
+    stopped: bool,
+    close: bool,
+}
+
+impl TripTracker {
+    /// Enregistre l'état de la voiture à la fin d'un tick. `lost` est le temps
+    /// perdu pendant ce tick, en secondes.
+    pub(crate) fn record(
+        &mut self,
+        tick: u64,
+        velocity: f32,
+        in_intersection: bool,
+        close: bool,
+        lost: f32,
+    ) {
+        if in_intersection && self.enter_tick.is_none() {
+            self.enter_tick = Some(tick);
+        }
+        self.min_vel = Some(self.min_vel.map_or(velocity, |v| v.min(velocity)));
+        self.max_vel = self.max_vel.max(velocity);
+
+        let stopped = velocity == 0.0;
+        if stopped && !self.stopped {
+            self.stops += 1;
+        }
+        self.stopped = stopped;
+
+        if close && !self.close {
+            self.close_calls += 1;
+        }
+        self.close = close;
+        self.delay += lost;
+    }
+
+    pub(crate) fn finish(
+        &self,
+        id: usize,
+        direction: Direction,
+        turning: Turning,
+        model: Model,
+        spawn_tick: u64,
+        exit_tick: u64,
+    ) -> TripRecord {
+        TripRecord {
+            id,
+            direction,
+            turning,
+            model,
+            spawn_tick,
+            enter_tick: self.enter_tick,
+            exit_tick,
+            min_vel: self.min_vel.unwrap_or_default(),
+            max_vel: self.max_vel,
+            stops: self.stops,
+            close_calls: self.close_calls,
+            delay: self.delay,
+        }
+    }
+}
+
+const CSV_HEADER: &str =
+    "id,direction,turning,model,spawn_tick,enter_tick,exit_tick,min_vel,max_vel,stops,close_calls,delay";
+
+/// Journal des trajets au format CSV, avec une ligne d'en-tête.
+pub fn trips_to_csv(trips: &[TripRecord]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for trip in trips {
+        // Les énumérations sont écrites avec les mêmes noms que dans le JSON.
+        let name = |value: serde_json::Value| value.as_str().unwrap_or_default().to_owned();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            trip.id,
+            name(serde_json::json!(trip.direction)),
+            name(serde_json::json!(trip.turning)),
+            name(serde_json::json!(trip.model)),
+            trip.spawn_tick,
+            trip.enter_tick.map(|t| t.to_string()).unwrap_or_default(),
+            trip.exit_tick,
+            trip.min_vel,
+            trip.max_vel,
+            trip.stops,
+            trip.close_calls,
+            trip.delay,
+        );
+    }
+    csv
+}
+
+/// Journal des trajets au format JSON, un tableau d'objets.
+pub fn trips_to_json(trips: &[TripRecord]) -> String {
+    serde_json::to_string_pretty(trips).expect("trip records are always serializable")
+}
+
+/// Écrit le journal des trajets dans `path`, en JSON si l'extension est `.json`
+/// et en CSV sinon.
+pub fn write_trips(path: impl AsRef<Path>, trips: &[TripRecord]) -> io::Result<()> {
+    let path = path.as_ref();
+    let content = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => trips_to_json(trips),
+        _ => trips_to_csv(trips),
+    };
+    fs::write(path, content)
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use macroquad::window::Conf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::circulation::{ControllerKind, Demand, SignalPlan};
+
+/// Cadence de la simulation. Un tick de `Clock` dure `1 / FPS` seconde.
+pub const FPS: u64 = 60;
+
+/// Nombre de secteurs sur chaque côté de la fenêtre.
+pub const GRID_SIZE: usize = 12;
+
+/// Paramètres de la simulation, lus depuis un fichier TOML/JSON et des options
+/// de la ligne de commande.
+///
+/// Les distances sont exprimées en largeurs de secteur pour qu'elles suivent
+/// `window_size`; les méthodes `*_px` les convertissent en pixels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// Côté de la fenêtre, en pixels.
+    pub window_size: i32,
+    pub speed_limit: f32,
+    pub cruise_speed: f32,
+    /// Écart entre deux emprises en dessous duquel on compte un presque accident.
+    pub close_call_gap: f32,
+    /// Écart entre deux emprises en dessous duquel on compte une collision; à
+    /// `0.0`, seules les emprises qui se chevauchent entrent en collision.
+    pub collision_gap: f32,
+    pub scan_distance: f32,
+    pub acceleration_distance: f32,
+    /// Tolérance des scanners, en pixels.
+    pub margin: f32,
+    /// Intervalle du mode aléatoire, en millisecondes.
+    pub random_interval: u64,
+    /// Nombre maximal de voitures en attente hors de la fenêtre, par approche.
+    /// Au-delà, les nouvelles demandes sont perdues.
+    pub queue_capacity: usize,
+    /// Graine du générateur; tirée au hasard si absente.
+    pub seed: Option<u64>,
+    /// Stratégie de gestion de l'intersection.
+    pub controller: ControllerKind,
+    /// Plan de feux du contrôleur `traffic_lights`.
+    pub signals: SignalPlan,
+    /// Modèle de demande, à la place du mode aléatoire s'il est activé.
+    pub demand: Demand,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            window_size: 1000,
+            speed_limit: 2.0,
+            cruise_speed: 0.7,
+            close_call_gap: 0.15,
+            collision_gap: 0.0,
+            scan_distance: 3.0,
+            acceleration_distance: 1.5,
+            margin: 3.0,
+            random_interval: 750,
+            queue_capacity: 20,
+            seed: None,
+            controller: ControllerKind::default(),
+            signals: SignalPlan::default(),
+            demand: Demand::default(),
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn sector_width(&self) -> f32 {
+        self.window_size as f32 / GRID_SIZE as f32
+    }
+
+    /// Distance parcourue en un tick par une voiture à `vel == 1.0`, en pixels.
+    pub fn max_velocity(&self) -> f32 {
+        (self.sector_width() * self.speed_limit) / FPS as f32
+    }
+
+    pub fn close_call_gap_px(&self) -> f32 {
+        self.close_call_gap * self.sector_width()
+    }
+
+    pub fn collision_gap_px(&self) -> f32 {
+        self.collision_gap * self.sector_width()
+    }
+
+    pub fn scan_distance_px(&self) -> f32 {
+        self.scan_distance * self.sector_width()
+    }
+
+    pub fn acceleration_distance_px(&self) -> f32 {
+        self.acceleration_distance * self.sector_width()
+    }
+
+    pub fn from_toml(content: &str) -> Result<SimulationConfig, ConfigError> {
+        let config: SimulationConfig =
+            toml::from_str(content).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json(content: &str) -> Result<SimulationConfig, ConfigError> {
+        let config: SimulationConfig =
+            serde_json::from_str(content).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Vérifie les contraintes que serde ne peut pas exprimer.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.window_size <= 0 {
+            return Err(ConfigError::Invalid("window_size must be positive".to_owned()));
+        }
+        if !(self.speed_limit > 0.0 && self.speed_limit.is_finite()) {
+            return Err(ConfigError::Invalid("speed_limit must be positive".to_owned()));
+        }
+        if !(self.cruise_speed > 0.0 && self.cruise_speed.is_finite()) {
+            return Err(ConfigError::Invalid("cruise_speed must be positive".to_owned()));
+        }
+        if !(self.scan_distance > 0.0 && self.scan_distance.is_finite()) {
+            return Err(ConfigError::Invalid("scan_distance must be positive".to_owned()));
+        }
+        if !(self.acceleration_distance > 0.0 && self.acceleration_distance.is_finite()) {
+            return Err(ConfigError::Invalid(
+                "acceleration_distance must be positive".to_owned(),
+            ));
+        }
+        if !(self.margin >= 0.0 && self.margin < self.sector_width()) {
+            return Err(ConfigError::Invalid(
+                "margin must satisfy 0 <= margin < sector width".to_owned(),
+            ));
+        }
+        if self.random_interval * FPS / 1000 == 0 {
+            return Err(ConfigError::Invalid(format!(
+                "random_interval must last at least one tick ({} ms)",
+                1000_u64.div_ceil(FPS)
+            )));
+        }
+        if self.queue_capacity == 0 {
+            return Err(ConfigError::Invalid("queue_capacity must be positive".to_owned()));
+        }
+        // Écrit ainsi pour rejeter aussi les écarts NaN.
+        if !(self.collision_gap >= 0.0 && self.close_call_gap >= self.collision_gap) {
+            return Err(ConfigError::Invalid(
+                "gaps must satisfy 0 <= collision_gap <= close_call_gap".to_owned(),
+            ));
+        }
+        let signals = &self.signals;
+        if signals.green <= 0.0 || signals.all_red < 0.0 || signals.min_green < 0.0 {
+            return Err(ConfigError::Invalid(
+                "signal durations must be positive".to_owned(),
+            ));
+        }
+        signals.validate().map_err(ConfigError::Invalid)?;
+        self.demand.validate().map_err(ConfigError::Invalid)
+    }
+
+    /// Charge un fichier `.toml` ou `.json` selon son extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<SimulationConfig, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| ConfigError::Io(format!("{}: {e}", path.display())))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => SimulationConfig::from_json(&content),
+            _ => SimulationConfig::from_toml(&content),
+        }
+    }
+
+    /// Modifie le champ `key` avec `value`, interprétée comme une valeur JSON
+    /// (`2.5`, `true`, `null`...). Les champs imbriqués s'écrivent avec des
+    /// points : `signals.all_red`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let mut fields =
+            serde_json::to_value(&*self).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        let field = key
+            .split('.')
+            .try_fold(&mut fields, |v, k| v.get_mut(k))
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_owned()))?;
+        *field = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+        let config: SimulationConfig =
+            serde_json::from_value(fields).map_err(|_| ConfigError::InvalidValue {
+                key: key.to_owned(),
+                value: value.to_owned(),
+            })?;
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    fn has_key(&self, key: &str) -> bool {
+        serde_json::to_value(self).is_ok_and(|fields| {
+            key.split('.')
+                .try_fold(&fields, |v, k| v.get(k))
+                .is_some()
+        })
+    }
+
+    /// Construit la configuration depuis la ligne de commande.
+    ///
+    /// `--config FICHIER` charge un fichier, puis chaque `--nom-du-champ VALEUR`
+    /// (ou `--section.nom-du-champ VALEUR`) écrase le champ correspondant. Les
+    /// arguments inconnus sont renvoyés à l'appelant.
+    pub fn from_args<I>(args: I) -> Result<(SimulationConfig, Vec<String>), ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let args: Vec<String> = args.into_iter().collect();
+        let mut config = match args.iter().position(|a| a == "--config") {
+            Some(i) => {
+                let path = args
+                    .get(i + 1)
+                    .ok_or_else(|| ConfigError::MissingValue("--config".to_owned()))?;
+                SimulationConfig::load(path)?
+            }
+            None => SimulationConfig::default(),
+        };
+
+        let mut rest = Vec::new();
+        let mut it = args.into_iter();
+        while let Some(arg) = it.next() {
+            if arg == "--config" {
+                it.next();
+                continue;
+            }
+            let key = arg.strip_prefix("--").unwrap_or_default().replace('-', "_");
+            if key.is_empty() || !config.has_key(&key) {
+                rest.push(arg);
+                continue;
+            }
+            let value = it
+                .next()
+                .ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+            config.set(&key, &value)?;
+        }
+        Ok((config, rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    UnknownKey(String),
+    InvalidValue { key: String, value: String },
+    MissingValue(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(msg) => write!(f, "cannot read config: {msg}"),
+            ConfigError::Parse(msg) => write!(f, "invalid config: {msg}"),
+            ConfigError::UnknownKey(key) => write!(f, "unknown config key `{key}`"),
+            ConfigError::InvalidValue { key, value } => {
+                write!(f, "invalid value `{value}` for `{key}`")
+            }
+            ConfigError::MissingValue(flag) => write!(f, "missing value for `{flag}`"),
+            ConfigError::Invalid(msg) => write!(f, "invalid config: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+pub fn window_conf(config: &SimulationConfig) -> Conf {
+    Conf {
+        window_title: "Smart-Road | Grit:lab".to_owned(),
+        window_width: config.window_size,
+        window_height: config.window_size,
+        window_resizable: false,
+        ..Default::default()
+    }
+}
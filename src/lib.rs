@@ -1,40 +1,11 @@
-pub mod config {
-    use macroquad::window::Conf;
-
-    pub const WINDOW_SIZE: i32 = 1000;
-    pub const FPS: u64 = 60;
-
-    pub const SECTOR_WIDTH: f32 = WINDOW_SIZE as f32 / 12.0;
-
-    pub const CLOSE_CALL_DISTANCE: f32 = SECTOR_WIDTH * 0.9;
-    pub const COLLISION_DISTANCE: f32 = SECTOR_WIDTH * 0.8;
-    pub const SCAN_DISTANCE: f32 = SECTOR_WIDTH * 3.0;
-    pub const ACCELERATION_DISTANCE: f32 = SCAN_DISTANCE / 2.0;
-
-    pub const SPEED_LIMIT: f32 = 2.0;
-    pub const MAX_VELOCITY: f32 = (SECTOR_WIDTH * SPEED_LIMIT) / FPS as f32;
-
-    pub const CRUISE_SPEED: f32 = SPEED_LIMIT * 0.35;
-    pub const MARGIN: f32 = 3.0;
-    pub const RANDOM_INTERVAL: u64 = (WINDOW_SIZE as u64 + 500) / SPEED_LIMIT as u64;
-
-    pub fn window_conf() -> Conf {
-        Conf {
-            window_title: "Smart-Road | Grit:lab".to_owned(),
-            window_width: WINDOW_SIZE,
-            window_height: WINDOW_SIZE,
-            window_resizable: false,
-            ..Default::default()
-        }
-    }
-}
+pub mod config;
 
 pub mod controls {
     use macroquad::prelude::*;
     use std::time::{Duration, Instant};
     use once_cell::sync::Lazy;
 
-    use crate::circulation::{Direction, State};
+    use crate::circulation::{Direction, Model, Replay, State, Turning};
 
     // Définir une constante pour la durée de l'attente
     const INPUT_COOLDOWN_DURATION: Duration = Duration::from_secs(1);
@@ -43,6 +14,30 @@ pub mod controls {
     static LAST_MOVEMENT_INPUT_TIME: Lazy<std::sync::Mutex<Instant>> =
         Lazy::new(|| std::sync::Mutex::new(Instant::now() - INPUT_COOLDOWN_DURATION));
 
+    /// Flèche du clavier et approche d'où vient la voiture qu'elle ajoute.
+    const ARROWS: [(KeyCode, Direction); 4] = [
+        (KeyCode::Up, Direction::South),
+        (KeyCode::Down, Direction::North),
+        (KeyCode::Right, Direction::West),
+        (KeyCode::Left, Direction::East),
+    ];
+
+    /// Virage choisi avec les touches de modification : `Shift` pour la gauche,
+    /// `Ctrl` pour la droite, `Alt` pour tout droit. Sans modificateur, la
+    /// voiture prend une voie libre au hasard.
+    fn chosen_turning() -> Option<Turning> {
+        let down = |left, right| is_key_down(left) || is_key_down(right);
+        if down(KeyCode::LeftShift, KeyCode::RightShift) {
+            Some(Turning::Left)
+        } else if down(KeyCode::LeftControl, KeyCode::RightControl) {
+            Some(Turning::Right)
+        } else if down(KeyCode::LeftAlt, KeyCode::RightAlt) {
+            Some(Turning::Straight)
+        } else {
+            None
+        }
+    }
+
     pub fn handle_input(state: &mut State) {
         let now = Instant::now();
 
@@ -54,28 +49,27 @@ pub mod controls {
                 //std::process::Sortir(0);
             }
 
-            if is_key_pressed(KeyCode::Up) {
-                state.add_car(Direction::South);
-                state.random = false;
-                *LAST_MOVEMENT_INPUT_TIME.lock().unwrap() = now; // Mettre à jour le temps de la dernière entrée de déplacement
+            for (key, direction) in ARROWS {
+                if is_key_pressed(key) {
+                    match chosen_turning() {
+                        Some(turning) => {
+                            let added = state.add_car_with(direction, turning, Model::Standard);
+                            if let Err(err) = added {
+                                eprintln!("cannot add a car: {err}");
+                            }
+                        }
+                        None => state.add_car(direction),
+                    }
+                    state.random = false;
+                    *LAST_MOVEMENT_INPUT_TIME.lock().unwrap() = now; // Mettre à jour le temps de la dernière entrée de déplacement
+                }
             }
 
-            if is_key_pressed(KeyCode::Down) {
-                state.add_car(Direction::North);
-                state.random = false;
-                *LAST_MOVEMENT_INPUT_TIME.lock().unwrap() = now; // Mettre à jour le temps de la dernière entrée de déplacement
-            }
-
-            if is_key_pressed(KeyCode::Right) {
-                state.add_car(Direction::West);
-                state.random = false;
-                *LAST_MOVEMENT_INPUT_TIME.lock().unwrap() = now; // Mettre à jour le temps de la dernière entrée de déplacement
-            }
-
-            if is_key_pressed(KeyCode::Left) {
-                state.add_car(Direction::East);
-                state.random = false;
-                *LAST_MOVEMENT_INPUT_TIME.lock().unwrap() = now; // Mettre à jour le temps de la dernière entrée de déplacement
+            if is_key_pressed(KeyCode::A) {
+                if let Err(err) = state.add_emergency_random() {
+                    eprintln!("cannot add an ambulance: {err}");
+                }
+                *LAST_MOVEMENT_INPUT_TIME.lock().unwrap() = now;
             }
         }
 
@@ -83,20 +77,115 @@ pub mod controls {
            // state.random = !state.random;
            state.add_car_random();
         }
+
+        if is_key_pressed(KeyCode::S) {
+            dump_snapshot(state);
+        }
+
+        if is_key_pressed(KeyCode::D) {
+            state.show_debug = !state.show_debug;
+        }
+        if is_key_pressed(KeyCode::H) {
+            state.show_hud = !state.show_hud;
+        }
+
+        // Pause, pas à pas et vitesse de la simulation.
+        if is_key_pressed(KeyCode::Space) {
+            state.playback.paused = !state.playback.paused;
+        }
+        if is_key_pressed(KeyCode::N) {
+            state.playback.step();
+        }
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            state.playback.scale(2.0);
+        }
+        if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            state.playback.scale(0.5);
+        }
+    }
+
+    /// Enregistre l'état courant dans `snapshot-<tick>.json`, à recharger avec
+    /// `--restore` ou `Snapshot::load`.
+    fn dump_snapshot(state: &State) {
+        let path = format!("snapshot-{}.json", state.clock.tick());
+        match state.snapshot().save(&path) {
+            Ok(()) => println!("State saved to {path}"),
+            Err(err) => eprintln!("error: cannot write {path}: {err}"),
+        }
+    }
+
+    /// Touches du mode relecture : `Espace` met en pause, les flèches gauche et
+    /// droite reculent ou avancent d'une seconde (d'un tick avec `Shift`), les
+    /// flèches haut et bas doublent ou divisent par deux la vitesse et
+    /// `Home` revient au début.
+    pub fn handle_replay_input(replay: &mut Replay) {
+        if is_key_pressed(KeyCode::Space) {
+            replay.paused = !replay.paused;
+        }
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        for (key, sign) in [(KeyCode::Left, -1.0), (KeyCode::Right, 1.0)] {
+            if is_key_pressed(key) {
+                if shift {
+                    replay.seek_frames(sign);
+                } else {
+                    replay.seek(sign);
+                }
+            }
+        }
+        if is_key_pressed(KeyCode::Up) {
+            replay.scale_speed(2.0);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            replay.scale_speed(0.5);
+        }
+        if is_key_pressed(KeyCode::Home) {
+            replay.restart();
+        }
     }
 }
 
 pub mod circulation {
     pub use car::*;
+    pub use clock::{Clock, Playback};
+    pub use controller::{
+        ControllerKind, ControllerSnapshot, IntersectionController, Reactive, TickContext,
+    };
+    pub use demand::{Demand, PerApproach, ProfilePoint, TurningSplit};
+    pub use footprint::{Footprint, OrientedRect};
+    pub use incident::{write_incidents, Incident, IncidentKind, IncidentTracker};
     pub use path::*;
-    pub use state::{Direction, State};
+    pub use profile::VehicleProfile;
+    pub use reservation::ReservationManager;
+    pub use scenario::{Scenario, ScenarioError, Spawn};
+    pub use snapshot::{Snapshot, SnapshotError};
+    pub use state::{Direction, SpawnError, State};
     pub use statistics::*;
+    pub use stop_sign::StopSign;
+    pub use trace::{
+        CarSpawn, CarState, Replay, Trace, TraceError, TraceFrame, TraceHeader, TraceWriter,
+    };
+    pub use traffic_light::{Movement, PhasePreset, Signal, SignalPhase, SignalPlan, TrafficLights};
+    pub use trip::{trips_to_csv, trips_to_json, write_trips, TripRecord};
 
     pub mod car;
+    pub mod clock;
+    pub mod controller;
+    pub mod demand;
+    pub mod emergency;
+    pub mod footprint;
+    pub mod incident;
     pub mod path;
+    pub mod profile;
+    pub mod reservation;
+    pub mod scenario;
+    pub mod snapshot;
     pub mod road;
     pub mod state;
     pub mod statistics;
+    pub mod stop_sign;
+    pub mod trace;
+    pub mod traffic_light;
+    pub mod trip;
 
     pub mod collision;
 }
@@ -118,4 +207,24 @@ pub mod representation {
     pub mod statistics;
 
     pub use statistics::representation_statistics;
+
+    pub mod signals;
+
+    pub use signals::representation_signals;
+
+    pub mod replay;
+
+    pub use replay::representation_replay;
+
+    pub mod playback;
+
+    pub use playback::representation_playback;
+
+    pub mod debug;
+
+    pub use debug::representation_debug;
+
+    pub mod hud;
+
+    pub use hud::representation_hud;
 }
\ No newline at end of file
@@ -1,43 +1,148 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use macroquad::prelude::*;
+use once_cell::sync::Lazy;
 
-use smart_road::config::{window_conf, FPS, RANDOM_INTERVAL};
-use smart_road::controls::handle_input;
+use smart_road::config::{SimulationConfig, FPS};
+use smart_road::controls::{handle_input, handle_replay_input};
 use smart_road::representation::car::representation_car;
 use smart_road::representation::roads::representation_textured_roads;
+use smart_road::representation::debug::representation_debug;
+use smart_road::representation::hud::representation_hud;
+use smart_road::representation::playback::representation_playback;
+use smart_road::representation::replay::representation_replay;
+use smart_road::representation::signals::representation_signals;
 use smart_road::representation::statistics::representation_statistics;
 use smart_road::circulation::*;
 
+struct Args {
+    config: SimulationConfig,
+    /// Fichier où écrire le journal des trajets à la fin de la simulation.
+    trips: Option<PathBuf>,
+    /// Trafic scripté, joué dès l'ouverture de la fenêtre.
+    scenario: Option<Scenario>,
+    /// Fichier où enregistrer la trace de la simulation.
+    trace: Option<PathBuf>,
+    /// Trace à rejouer à la place d'une simulation.
+    replay: Option<Trace>,
+    /// État enregistré avec la touche `S`, d'où reprendre la simulation.
+    restore: Option<Snapshot>,
+}
+
+// La configuration est lue avant l'ouverture de la fenêtre, dont elle fixe la taille.
+static ARGS: Lazy<Args> = Lazy::new(|| {
+    let exit = |err: String| -> ! {
+        eprintln!("error: {err}");
+        process::exit(2);
+    };
+    let (config, rest) =
+        SimulationConfig::from_args(env::args().skip(1)).unwrap_or_else(|err| exit(err.to_string()));
+    let mut args = Args {
+        config,
+        trips: None,
+        scenario: None,
+        trace: None,
+        replay: None,
+        restore: None,
+    };
+    let mut it = rest.into_iter();
+    while let Some(flag) = it.next() {
+        let mut value = || {
+            it.next()
+                .unwrap_or_else(|| exit(format!("missing value for `{flag}`")))
+        };
+        match flag.as_str() {
+            "--trips" => args.trips = Some(PathBuf::from(value())),
+            "--scenario" => {
+                let scenario = Scenario::load(value()).unwrap_or_else(|err| exit(err.to_string()));
+                args.scenario = Some(scenario);
+            }
+            "--trace" => args.trace = Some(PathBuf::from(value())),
+            "--replay" => {
+                let trace = Trace::load(value()).unwrap_or_else(|err| exit(err.to_string()));
+                args.replay = Some(trace);
+            }
+            "--restore" => {
+                let snapshot = Snapshot::load(value()).unwrap_or_else(|err| exit(err.to_string()));
+                args.restore = Some(snapshot);
+            }
+            _ => exit(format!("unknown argument `{flag}`")),
+        }
+    }
+    // La fenêtre doit avoir la taille de la simulation enregistrée.
+    if let Some(trace) = &args.replay {
+        args.config = trace.header.config.clone();
+    }
+    if let Some(snapshot) = &args.restore {
+        args.config = snapshot.config().clone();
+    }
+    args
+});
+
+fn window_conf() -> Conf {
+    smart_road::config::window_conf(&ARGS.config)
+}
+
 #[macroquad::main(window_conf)]
 async fn main() {
     let textures = smart_road::representation::textures::Textures::load().await;
-    let mut state = State::new();
+    if let Some(trace) = &ARGS.replay {
+        replay(Replay::new(trace.clone()), &textures).await;
+    }
+    let mut state = match &ARGS.restore {
+        Some(snapshot) => State::restore(snapshot.clone()),
+        None => State::new(ARGS.config.clone()),
+    };
+    if let Some(scenario) = &ARGS.scenario {
+        state.load_scenario(scenario);
+    }
+    println!("Seed: {}", state.seed);
+    if let Some(path) = &ARGS.trace {
+        if let Err(err) = state.record_trace(path) {
+            eprintln!("error: cannot write {}: {err}", path.display());
+        }
+    }
 
     let frame_duration = Duration::from_micros(1_000_000 / FPS);
     let mut last_frame_time = Instant::now();
 
-    let mut random_timer = Instant::now();
-    let random_interval = Duration::from_millis(RANDOM_INTERVAL);
+    let mut trips_written = false;
+
+    // Intervalle du mode aléatoire en ticks, pour suivre la vitesse de la simulation.
+    let random_interval = (state.config.random_interval * FPS / 1000).max(1);
 
     loop {
         clear_background(BLACK);
         handle_input(&mut state);
         if !state.show_final_statistics {
             representation_textured_roads(&textures);
+            representation_signals(&state);
 
-            if state.random && random_timer.elapsed() > random_interval {
-                state.add_car_random();
-                random_timer = Instant::now();
+            for _ in 0..state.playback.ticks() {
+                if state.config.demand.enabled {
+                    state.add_demand();
+                } else if state.random && state.clock.tick().is_multiple_of(random_interval) {
+                    state.add_car_random();
+                }
+                state.update();
             }
-            state.update();
 
             for road in &state.roads {
                 for car in road.cars.iter().flatten() {
                     representation_car(car, &textures.cars);
                 }
             }
+            if state.show_debug {
+                representation_debug(&state);
+            }
+            if state.show_hud {
+                representation_hud(&state);
+            }
+            representation_playback(&state.playback);
             let elapsed = last_frame_time.elapsed();
             if elapsed < frame_duration {
                 thread::sleep(frame_duration - elapsed);
@@ -47,7 +152,32 @@ async fn main() {
         } else {
             state.stats.set_max_vehicles(state.total_cars);
             representation_statistics(&state.stats);
+            if let (Some(path), false) = (&ARGS.trips, trips_written) {
+                if let Err(err) = write_trips(path, state.stats.trips()) {
+                    eprintln!("error: cannot write {}: {err}", path.display());
+                }
+                trips_written = true;
+            }
+            if let Err(err) = state.finish_trace() {
+                eprintln!("error: cannot write trace: {err}");
+            }
+        }
+        next_frame().await
+    }
+}
+
+/// Rejoue une trace enregistrée avec `--trace`, jusqu'à la fermeture de la fenêtre.
+async fn replay(mut replay: Replay, textures: &smart_road::representation::Textures) -> ! {
+    loop {
+        clear_background(BLACK);
+        handle_replay_input(&mut replay);
+        replay.advance(get_frame_time());
+
+        representation_textured_roads(textures);
+        for car in &replay.cars() {
+            representation_car(car, &textures.cars);
         }
+        representation_replay(&replay);
         next_frame().await
     }
 }
@@ -1,41 +1,61 @@
 use crate::circulation::Model;
-use crate::{
-    config::SECTOR_WIDTH,
-    circulation::{car::Car, Moving},
-};
+use crate::circulation::{car::Car, Moving};
 use macroquad::prelude::*;
 pub fn representation_car(car: &Car, textures: &[Texture2D]) {
     let texture = match car.model {
         Model::Standard => &textures[0],
         Model::Sport => &textures[1],
         Model::TaxiVert => &textures[2],
+        Model::Ambulance => &textures[3],
+        Model::GarbageTruck => &textures[4],
        // Model::TaxiOrange => &textures[3],
        
     };
-    // Déterminer quel sprite utiliser en fonction de la direction de la voiture.
-    let rotation: f32 = match car.moving {
-        Moving::Up => 0.0,
-        Moving::Left => -90.0,
-        Moving::Down => -180.0,
-        Moving::Right => -270.0,
-    };
-    let src_rect = Rect::new(0.0, 0.0, SECTOR_WIDTH, SECTOR_WIDTH);
+    let sector_width = car.sector_width();
     // réduire à l'échelle de 80%
-    let scaled_size = SECTOR_WIDTH * 0.9;
-    // Calculer la position pour centrer la voiture dans le secteur
-    let center_x = car.x + (SECTOR_WIDTH - scaled_size) / 2.0;
-    let center_y = car.y + (SECTOR_WIDTH - scaled_size) / 2.0;
+    let scaled_size = sector_width * 0.9;
+    // Un véhicule long est dessiné par tranches, une par case occupée, pour
+    // suivre son `Path` dans les virages. La première case est l'avant.
+    let span = car.span();
+    let (length, src_size) = if span == 1 {
+        (scaled_size, Vec2::splat(sector_width))
+    } else {
+        (sector_width, texture.size())
+    };
+    let slice = src_size.y / span as f32;
+    for (k, ((x, y), moving)) in car.cells().into_iter().enumerate() {
+        // Déterminer quel sprite utiliser en fonction de la direction de la voiture.
+        let rotation: f32 = match moving {
+            Moving::Up => 0.0,
+            Moving::Left => -90.0,
+            Moving::Down => -180.0,
+            Moving::Right => -270.0,
+        } + match car.model {
+            // Ces sprites sont dessinés vers le bas.
+            Model::Ambulance | Model::GarbageTruck => 180.0,
+            _ => 0.0,
+        };
+        let src_rect = match car.model {
+            Model::Ambulance | Model::GarbageTruck => {
+                Rect::new(0.0, slice * (span - 1 - k) as f32, src_size.x, slice)
+            }
+            _ => Rect::new(0.0, slice * k as f32, src_size.x, slice),
+        };
+        // Calculer la position pour centrer la voiture dans le secteur
+        let center_x = x + (sector_width - scaled_size) / 2.0;
+        let center_y = y + (sector_width - length) / 2.0;
 
-    draw_texture_ex(
-        texture,
-        center_x,
-        center_y,
-        WHITE,
-        DrawTextureParams {
-            source: Some(src_rect),
-            rotation: rotation.to_radians(),
-            dest_size: Some(Vec2::new(scaled_size, scaled_size)), // Définir à 80% de la taille du secteur
-            ..Default::default()
-        },
-    );
+        draw_texture_ex(
+            texture,
+            center_x,
+            center_y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(src_rect),
+                rotation: rotation.to_radians(),
+                dest_size: Some(Vec2::new(scaled_size, length)), // Définir à 80% de la taille du secteur
+                ..Default::default()
+            },
+        );
+    }
 }
@@ -0,0 +1,102 @@
+use crate::circulation::{Car, IncidentKind, Sector, State};
+use crate::config::GRID_SIZE;
+use macroquad::prelude::*;
+
+const GRID: Color = Color::new(1.0, 1.0, 1.0, 0.15);
+const OCCUPIED: Color = Color::new(1.0, 1.0, 0.0, 0.25);
+const SCAN: Color = Color::new(0.3, 0.8, 1.0, 0.5);
+
+/// Couleur propre à chaque voiture, pour distinguer les `Path`.
+fn car_color(car: &Car) -> Color {
+    const COLORS: [Color; 6] = [SKYBLUE, LIME, PINK, GOLD, VIOLET, ORANGE];
+    COLORS[car.id % COLORS.len()]
+}
+
+fn sector_center(sector: &Sector, sector_width: f32) -> (f32, f32) {
+    (
+        (sector.get_x() as f32 + 0.5) * sector_width,
+        (sector.get_y() as f32 + 0.5) * sector_width,
+    )
+}
+
+/// Superpose aux voitures ce que voient leurs capteurs : la grille des
+/// secteurs et ceux qui sont occupés, le reste du `Path` de chaque voiture,
+/// la portée de `ray_casting` (`scan_distance`) et de `forward_scan`
+/// (`acceleration_distance`), la voiture pour laquelle chacune freine et les
+/// incidents en cours.
+pub fn representation_debug(state: &State) {
+    let config = &state.config;
+    let sector_width = config.sector_width();
+    let size = sector_width * GRID_SIZE as f32;
+    let cars = state.get_all_cars();
+
+    for i in 0..=GRID_SIZE {
+        let at = i as f32 * sector_width;
+        draw_line(at, 0.0, at, size, 1.0, GRID);
+        draw_line(0.0, at, size, at, 1.0, GRID);
+    }
+    for sector in cars.iter().flat_map(|c| c.occupied_sectors()) {
+        let (x, y) = (sector.get_x() as f32, sector.get_y() as f32);
+        draw_rectangle(
+            x * sector_width,
+            y * sector_width,
+            sector_width,
+            sector_width,
+            OCCUPIED,
+        );
+    }
+
+    for car in &cars {
+        let color = car_color(car);
+        let (x, y) = car.center_car();
+
+        // Reste du trajet, du secteur de tête à la sortie.
+        let remaining = &car.path.sectors[car.index.min(car.path.sectors.len() - 1)..];
+        let mut from = (x, y);
+        for sector in remaining {
+            let to = sector_center(sector, sector_width);
+            draw_line(from.0, from.1, to.0, to.1, 2.0, color);
+            draw_circle(to.0, to.1, 3.0, color);
+            from = to;
+        }
+
+        // Portées des capteurs : le cercle de `ray_casting` et le rayon de
+        // `forward_scan` au-delà duquel la voiture accélère.
+        draw_circle_lines(x, y, config.scan_distance_px(), 1.0, SCAN);
+        let (dx, dy) = car.moving.axis();
+        let reach = config.acceleration_distance_px();
+        draw_line(x, y, x + dx * reach, y + dy * reach, 2.0, SCAN);
+
+        // Voitures pour lesquelles elle freine.
+        if let Some((other, distance)) = car.car_ahead(&cars, config) {
+            if distance <= reach {
+                let (x2, y2) = other.center_car();
+                draw_line(x, y, x2, y2, 3.0, RED);
+            }
+        }
+        if let Some((other, _)) = car.crossing_car(&cars, config) {
+            let (x2, y2) = other.center_car();
+            draw_line(x, y, x2, y2, 3.0, MAGENTA);
+        }
+    }
+
+    // Incidents en cours, autour des deux voitures.
+    for incident in state
+        .stats
+        .incidents()
+        .iter()
+        .filter(|i| i.end_tick.is_none())
+    {
+        let color = match incident.kind {
+            IncidentKind::CloseCall => ORANGE,
+            IncidentKind::Collision => RED,
+        };
+        for car in cars
+            .iter()
+            .filter(|c| c.id == incident.cars.0 || c.id == incident.cars.1)
+        {
+            let (x, y) = car.center_car();
+            draw_circle_lines(x, y, sector_width * 0.6, 3.0, color);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::circulation::{State, TRIP_HISTORY};
+use crate::config::FPS;
+use crate::representation::FONT_SIZE;
+use macroquad::prelude::*;
+
+/// Fenêtre glissante du débit et du temps de traversée moyen, en ticks.
+const WINDOW: u64 = 60 * FPS;
+/// Largeur d'une barre de l'historique du débit, en ticks.
+const BIN: u64 = 10 * FPS;
+/// Nombre de barres de l'historique : les cinq dernières minutes.
+const BINS: usize = 30;
+// Un état restauré n'a que les trajets des `TRIP_HISTORY` derniers ticks.
+const _: () = assert!(WINDOW <= TRIP_HISTORY && (BINS as u64 + 1) * BIN <= TRIP_HISTORY);
+
+const BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+
+/// Statistiques en cours de simulation, en haut à gauche de la fenêtre, et
+/// historique du débit de sortie.
+pub fn representation_hud(state: &State) {
+    let now = state.clock.tick();
+    let stats = &state.stats;
+    let in_intersection = state
+        .roads
+        .iter()
+        .flat_map(|r| r.cars.iter().flatten())
+        .filter(|c| c.in_intersection())
+        .count();
+    let lines = [
+        format!("Time: {:.1} s", state.clock.elapsed()),
+        format!("In intersection: {in_intersection}"),
+        format!(
+            "Spawned: {}  Exited: {}  Queued: {}",
+            state.total_cars,
+            stats.exited(),
+            state.queued()
+        ),
+        format!("Throughput: {:.1} veh/min", stats.throughput(now, WINDOW)),
+        format!(
+            "Mean time (1 min): {:.2} s",
+            stats.recent_mean_time(now, WINDOW)
+        ),
+        format!(
+            "Close calls: {}  Collisions: {}",
+            stats.close_calls(),
+            stats.collisions()
+        ),
+    ];
+
+    let (x, y) = (10.0, 10.0);
+    let width = 300.0;
+    let chart_height = 50.0;
+    let text_height = lines.len() as f32 * FONT_SIZE;
+    draw_rectangle(
+        x - 5.0,
+        y - 5.0,
+        width + 10.0,
+        text_height + chart_height + FONT_SIZE + 15.0,
+        BACKGROUND,
+    );
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x, y + (i as f32 + 0.8) * FONT_SIZE, FONT_SIZE, WHITE);
+    }
+
+    // Débit de chaque tranche, en voitures par minute.
+    let history = stats.exit_history(now, BIN, BINS);
+    let per_minute = (60 * FPS / BIN) as f32;
+    let top = y + text_height + 5.0;
+    let peak = history.iter().copied().max().unwrap_or(0).max(1) as f32 * per_minute;
+    draw_text(
+        &format!("Throughput, last 5 min (max {peak:.0} veh/min)"),
+        x,
+        top + 0.8 * FONT_SIZE,
+        FONT_SIZE * 0.8,
+        GRAY,
+    );
+    let bottom = top + FONT_SIZE + chart_height;
+    draw_line(x, bottom, x + width, bottom, 1.0, GRAY);
+    let step = width / (BINS - 1) as f32;
+    let points: Vec<(f32, f32)> = history
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| {
+            let value = n as f32 * per_minute;
+            (x + i as f32 * step, bottom - value / peak * chart_height)
+        })
+        .collect();
+    for pair in points.windows(2) {
+        draw_line(pair[0].0, pair[0].1, pair[1].0, pair[1].1, 2.0, GREEN);
+    }
+}
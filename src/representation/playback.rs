@@ -0,0 +1,21 @@
+use crate::circulation::Playback;
+use crate::representation::FONT_SIZE;
+use macroquad::prelude::*;
+
+/// Affiche la cadence de la simulation en haut à droite de la fenêtre.
+pub fn representation_playback(playback: &Playback) {
+    let text = if playback.paused {
+        "Paused (Space: resume, N: step)".to_owned()
+    } else {
+        format!("x{} (+/-: speed, Space: pause)", playback.time_scale())
+    };
+    let width = measure_text(&text, None, FONT_SIZE as u16, 1.0).width;
+    let color = if playback.paused { YELLOW } else { WHITE };
+    draw_text(
+        &text,
+        screen_width() - width - 10.0,
+        FONT_SIZE,
+        FONT_SIZE,
+        color,
+    );
+}
@@ -0,0 +1,47 @@
+use crate::circulation::{IncidentKind, Replay};
+use crate::config::FPS;
+use crate::representation::FONT_SIZE;
+use macroquad::prelude::*;
+
+/// Encadre les voitures impliquées dans un incident en cours et affiche
+/// l'état de la lecture en haut de la fenêtre.
+pub fn representation_replay(replay: &Replay) {
+    let cars = replay.cars();
+    for incident in replay.active_incidents() {
+        let color = match incident.kind {
+            IncidentKind::CloseCall => ORANGE,
+            IncidentKind::Collision => RED,
+        };
+        for car in cars
+            .iter()
+            .filter(|c| c.id == incident.cars.0 || c.id == incident.cars.1)
+        {
+            let sector_width = car.sector_width();
+            for ((x, y), _) in car.cells() {
+                draw_rectangle_lines(x, y, sector_width, sector_width, 3.0, color);
+            }
+        }
+    }
+
+    let tick = replay.tick();
+    let mut status = format!(
+        "tick {tick} ({:.1} s)  x{}{}",
+        tick as f32 / FPS as f32,
+        replay.speed(),
+        if replay.paused { "  paused" } else { "" },
+    );
+    if let Some(incident) = replay.last_incident() {
+        status += &format!(
+            "  last incident: {:?} {}/{} at tick {}",
+            incident.kind, incident.cars.0, incident.cars.1, incident.start_tick
+        );
+    }
+    draw_rectangle(
+        0.0,
+        0.0,
+        screen_width(),
+        FONT_SIZE * 1.5,
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+    draw_text(&status, 10.0, FONT_SIZE, FONT_SIZE, WHITE);
+}
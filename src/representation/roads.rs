@@ -3,5 +3,14 @@ use crate::representation::textures::Textures;
 use macroquad::prelude::*;
 
 pub fn representation_textured_roads(textures: &Textures) {
-    draw_texture(&textures.bg, 0.0, 0.0, WHITE);
+    draw_texture_ex(
+        &textures.bg,
+        0.0,
+        0.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(Vec2::new(screen_width(), screen_height())),
+            ..Default::default()
+        },
+    );
 }
@@ -0,0 +1,39 @@
+use crate::circulation::{Direction, Path, Signal, State, Turning};
+use macroquad::prelude::*;
+
+/// Dessine un feu au bout de chaque voie, sur la ligne d'arrêt, si le
+/// contrôleur en utilise.
+pub fn representation_signals(state: &State) {
+    let sector_width = state.config.sector_width();
+    let radius = sector_width * 0.12;
+
+    for direction in [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ] {
+        for turning in [Turning::Left, Turning::Straight, Turning::Right] {
+            let Some(signal) = state.controller.signal(&direction, &turning) else {
+                return;
+            };
+            let path = Path::new(&direction, &turning);
+            let entry = path.entry_index();
+            // Le feu est placé sur le bord du secteur d'arrêt, côté intersection.
+            let stop = &path.sectors[entry - 1];
+            let next = &path.sectors[entry];
+            let dx = next.get_x() as f32 - stop.get_x() as f32;
+            let dy = next.get_y() as f32 - stop.get_y() as f32;
+            let half = sector_width / 2.0;
+            let x = (stop.get_x() as f32 + 0.5) * sector_width + dx * (half - radius);
+            let y = (stop.get_y() as f32 + 0.5) * sector_width + dy * (half - radius);
+
+            let color = match signal {
+                Signal::Green => GREEN,
+                Signal::Red => RED,
+            };
+            draw_circle(x, y, radius + 2.0, BLACK);
+            draw_circle(x, y, radius, color);
+        }
+    }
+}
@@ -1,88 +1,33 @@
 use crate::circulation::Statistics;
 use macroquad::prelude::*;
 
-use crate::config::{SECTOR_WIDTH, WINDOW_SIZE};
 use crate::representation::{FONT_SIZE, TITLE_SIZE};
 
-const TEXT_X_POS: f32 = CENTER_Y - 100.0;
-const CENTER_Y: f32 = WINDOW_SIZE as f32 / 2.0;
-
 pub fn representation_statistics(stats: &Statistics) {
+    let center_y = screen_height() / 2.0;
+    let text_x_pos = screen_width() / 2.0 - 100.0;
+
     // Rendre une rectangle translucide comme toile de fond.
-    draw_rectangle(0.0, 0.0, WINDOW_SIZE as f32, WINDOW_SIZE as f32, BLACK);
+    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), BLACK);
+
+    let rows = stats.summary();
+    // Centrer le bloc de texte verticalement, titre compris.
+    let line_height = FONT_SIZE;
+    let top = center_y - (rows.len() as f32 + 1.0) * line_height / 2.0;
 
     // Afficher le titre
-    draw_text(
-        "Final Statistics:",
-        TEXT_X_POS,
-        CENTER_Y - 80.0,
-        TITLE_SIZE,
-        WHITE,
-    );
+    draw_text("Final Statistics:", text_x_pos, top, TITLE_SIZE, WHITE);
 
     // Afficher les statistiques
-    draw_text(
-        &format!("Max Vehicles: {} cars", stats.max_vehicles()),
-        TEXT_X_POS,
-        CENTER_Y - 60.0,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!(
-            "Max Velocity: {} px/s",
-            round_to_tenth(stats.max_velocity() * SECTOR_WIDTH)
-        ),
-        TEXT_X_POS,
-        CENTER_Y - 40.0,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!(
-            "Min Velocity: {} px/s",
-            round_to_tenth(stats.min_velocity() * SECTOR_WIDTH)
-        ),
-        TEXT_X_POS,
-        CENTER_Y - 20.0,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!("Max Time: {} s", round_to_tenth(stats.max_time())),
-        TEXT_X_POS,
-        CENTER_Y,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!("Min Time: {} s", round_to_tenth(stats.min_time())),
-        TEXT_X_POS,
-        CENTER_Y + 20.0,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!("Close Calls: {}", stats.close_calls()),
-        TEXT_X_POS,
-        CENTER_Y + 40.0,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!("Collisions: {}", stats.collisions()),
-        TEXT_X_POS,
-        CENTER_Y + 60.0,
-        FONT_SIZE,
-        WHITE,
-    );
-    draw_text(
-        &format!("Average Time: {} s", round_to_tenth(stats.average_time())),
-        TEXT_X_POS,
-        CENTER_Y + 80.0,
-        FONT_SIZE,
-        WHITE,
-    );
+    for (i, (label, value)) in rows.iter().enumerate() {
+        draw_text(
+            &format!("{label}: {value}"),
+            text_x_pos,
+            top + (i as f32 + 1.0) * line_height,
+            FONT_SIZE,
+            WHITE,
+        );
+    }
 }
 
 pub fn round_to_tenth(num: f32) -> f32 {
@@ -1,9 +1,11 @@
 use macroquad::prelude::*;
 
-const CAR_SPRITES: [&str; 3] = [
+const CAR_SPRITES: [&str; 5] = [
     "assets/cars/1.png",
     "assets/cars/2.png",
     "assets/cars/6.png",
+    "assets/cars/ambulance.png",
+    "assets/cars/garbage_truck.png",
      //"assets/cars/4.png",
     
 ];
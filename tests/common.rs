@@ -1,8 +1,22 @@
 #![allow(dead_code)]
 
-use smart_road::circulation::State;
+use smart_road::circulation::{Car, ControllerKind, State};
+use smart_road::config::{SimulationConfig, FPS};
+
+/// Graine fixe pour que la simulation de test soit reproductible.
+pub const SEED: u64 = 2023;
+
+/// État vide géré par `controller`, avec la graine `SEED`.
+pub fn seeded(controller: ControllerKind) -> State {
+    State::new(SimulationConfig {
+        controller,
+        seed: Some(SEED),
+        ..SimulationConfig::default()
+    })
+}
+
 pub async fn setup() -> State {
-    let mut state = State::new();
+    let mut state = State::with_seed(SEED);
     for _ in 0..=50 {
         for _ in 0..2 {
             state.add_car_random();
@@ -25,5 +39,57 @@ pub async fn setup() -> State {
             state.update();
         }
     }
-    return state;
+    state
+}
+
+/// Toutes les voitures sur les routes, y compris la voie de droite.
+pub fn cars_on_roads(state: &State) -> Vec<Car> {
+    state
+        .roads
+        .iter()
+        .flat_map(|r| r.cars.iter().flatten().cloned())
+        .collect()
+}
+
+/// Plus petit écart entre les emprises de deux voitures sur les routes.
+pub fn min_gap(state: &State) -> f32 {
+    let cars = cars_on_roads(state);
+    let mut min = f32::MAX;
+    for (i, a) in cars.iter().enumerate() {
+        for b in &cars[i + 1..] {
+            min = min.min(a.gap(b));
+        }
+    }
+    min
+}
+
+/// `true` si les cases des deux voitures, d'un secteur de côté, se chevauchent.
+pub fn overlapping(a: &Car, b: &Car) -> bool {
+    let width = a.sector_width() - 1.0;
+    a.cells().iter().any(|((ax, ay), _)| {
+        b.cells()
+            .iter()
+            .any(|((bx, by), _)| (ax - bx).abs() < width && (ay - by).abs() < width)
+    })
+}
+
+/// Simule `seconds` secondes en mode aléatoire et retourne le plus petit écart
+/// observé entre les emprises de deux voitures.
+pub fn run_random(state: &mut State, seconds: u64) -> f32 {
+    run_with(state, seconds, State::add_car_random)
+}
+
+/// Comme `run_random`, mais `spawn` ajoute les voitures à chaque intervalle
+/// du mode aléatoire. Le tick de la demande est `state.clock.tick()`.
+pub fn run_with(state: &mut State, seconds: u64, mut spawn: impl FnMut(&mut State)) -> f32 {
+    let interval = state.config.random_interval * FPS / 1000;
+    let mut min = f32::MAX;
+    while state.clock.tick() < seconds * FPS {
+        if state.clock.tick().is_multiple_of(interval) {
+            spawn(state);
+        }
+        state.update();
+        min = min.min(min_gap(state));
+    }
+    min
 }
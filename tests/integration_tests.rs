@@ -1,9 +1,85 @@
 mod common;
 
-mod test_config {}
+mod test_config {
+    use smart_road::config::{ConfigError, SimulationConfig};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = SimulationConfig::default();
+
+        assert_eq!(config.sector_width(), 1000.0 / 12.0);
+        assert_eq!(config.scan_distance_px(), config.sector_width() * 3.0);
+        assert_eq!(config.max_velocity(), config.sector_width() * 2.0 / 60.0);
+        assert_eq!(config.seed, None);
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = SimulationConfig::from_toml("speed_limit = 3.0\nseed = 12").unwrap();
+
+        assert_eq!(config.speed_limit, 3.0);
+        assert_eq!(config.seed, Some(12));
+        assert_eq!(config.window_size, SimulationConfig::default().window_size);
+        assert!(SimulationConfig::from_toml("scan_range = 2.0").is_err());
+    }
+
+    #[test]
+    fn test_from_args() {
+        let (config, rest) = SimulationConfig::from_args(args(&[
+            "--scan-distance",
+            "2.5",
+            "--cars",
+            "10",
+            "--seed",
+            "3",
+        ]))
+        .unwrap();
+
+        assert_eq!(config.scan_distance, 2.5);
+        assert_eq!(config.seed, Some(3));
+        assert_eq!(rest, args(&["--cars", "10"]));
+
+        assert_eq!(
+            SimulationConfig::from_args(args(&["--margin", "wide"])),
+            Err(ConfigError::InvalidValue {
+                key: "margin".to_owned(),
+                value: "wide".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn sizes_and_speeds_are_validated() {
+        let mut config = SimulationConfig::default();
+        for (key, value) in [
+            ("window_size", "0"),
+            ("speed_limit", "0"),
+            ("cruise_speed", "0"),
+            ("scan_distance", "-1"),
+            ("acceleration_distance", "0"),
+            ("margin", "100"),
+            ("random_interval", "10"),
+            ("queue_capacity", "0"),
+        ] {
+            assert!(matches!(config.set(key, value), Err(ConfigError::Invalid(_))), "{key} = {value}");
+        }
+        assert_eq!(config, SimulationConfig::default());
+        assert!(config.set("margin", "0").is_ok());
+        assert!(config.set("random_interval", "17").is_ok());
+        // JSON ne sait pas écrire NaN, TOML si.
+        for field in ["close_call_gap = nan", "collision_gap = nan", "cruise_speed = inf"] {
+            assert!(matches!(SimulationConfig::from_toml(field), Err(ConfigError::Invalid(_))), "{field}");
+        }
+    }
+}
 mod test_state {
     use crate::common;
     use smart_road::circulation::state::*;
+    use smart_road::circulation::{Car, Clock, IntersectionController, Model, TickContext, Turning};
 
     #[test]
     fn test_constructor() {
@@ -43,6 +119,134 @@ mod test_state {
         }
     }
 
+    #[test]
+    fn test_add_car_with() {
+        let mut state = State::default();
+        let id = state.add_car_with(Direction::South, Turning::Left, Model::GarbageTruck);
+        assert_eq!(id, Ok(0));
+        let car = &state.roads[Direction::South.road()].cars[0][0];
+        assert_eq!((car.id, car.turning.clone(), car.model.clone()), (0, Turning::Left, Model::GarbageTruck));
+
+        assert_eq!(
+            state.add_car_with(Direction::South, Turning::Left, Model::Standard),
+            Err(SpawnError::LaneOccupied)
+        );
+        assert_eq!(state.add_car_with(Direction::South, Turning::Right, Model::Standard), Ok(1));
+        assert_eq!(state.queued(), 0);
+    }
+
+    #[test]
+    fn test_add_car_with_jammed() {
+        let mut state = State::default();
+        for direction in [Direction::North, Direction::East] {
+            for turning in [Turning::Left, Turning::Straight, Turning::Right] {
+                state.add_car_with(direction.clone(), turning, Model::Standard).unwrap();
+            }
+        }
+        state.roads.iter_mut().flat_map(|r| r.cars.iter_mut().flatten()).for_each(|c| c.stop());
+
+        assert_eq!(
+            state.add_car_with(Direction::West, Turning::Left, Model::Standard),
+            Err(SpawnError::TrafficJammed)
+        );
+        assert!(state.add_car_with(Direction::West, Turning::Left, Model::Ambulance).is_ok());
+    }
+
+    #[test]
+    fn test_add_car_with_does_not_jump_the_queue() {
+        let mut state = State::default();
+        for _ in 0..4 {
+            state.add_car(Direction::North);
+        }
+        assert_eq!(state.queue_len(&Direction::North), 1);
+        // La voie de droite se libère avant que la file soit servie.
+        state.roads[0].cars[2].clear();
+        assert_eq!(
+            state.add_car_with(Direction::North, Turning::Right, Model::Standard),
+            Err(SpawnError::QueueNotEmpty)
+        );
+        assert!(state.add_car_with(Direction::North, Turning::Right, Model::Ambulance).is_ok());
+        assert!(state.add_car_with(Direction::East, Turning::Right, Model::Standard).is_ok());
+    }
+
+    #[test]
+    fn test_car_ahead() {
+        let mut state = State::default();
+        let leader = state.add_car_with(Direction::North, Turning::Straight, Model::Standard).unwrap();
+        let follower = loop {
+            state.update();
+            if let Ok(id) = state.add_car_with(Direction::North, Turning::Straight, Model::Standard) {
+                break id;
+            }
+        };
+        let cars = state.get_all_cars();
+        let car = |id| cars.iter().find(|c| c.id == id).unwrap();
+        let (ahead, distance) = car(follower).car_ahead(&cars, &state.config).unwrap();
+        assert_eq!(ahead.id, leader);
+        assert_eq!(distance, car(follower).calc_dist(car(leader)));
+        assert!(car(leader).car_ahead(&cars, &state.config).is_none());
+    }
+
+    /// Avance sans capteurs : seul `move_in_path` peut arrêter une voiture.
+    #[derive(Debug)]
+    struct Blind;
+
+    impl IntersectionController for Blind {
+        fn name(&self) -> &'static str {
+            "blind"
+        }
+
+        fn drive(&mut self, car: &mut Car, cars: &[Car], ctx: &TickContext) {
+            car.advance(cars, ctx.config);
+        }
+    }
+
+    #[test]
+    fn test_car_ahead_overhanging() {
+        let config = smart_road::config::SimulationConfig::default();
+        let width = config.sector_width();
+        let mut follower = Car::new(Direction::North, Turning::Straight, Model::Standard, 0, 0, &config);
+        follower.y = 2.0 * width;
+        // Voiture qui sort du secteur de devant par le côté : son centre est
+        // hors de la bande balayée, mais pas sa carrosserie.
+        let mut crossing = Car::new(Direction::West, Turning::Straight, Model::Standard, 1, 0, &config);
+        (crossing.x, crossing.y) = (follower.x + width / 2.0, 3.0 * width);
+
+        let cars = [follower.clone(), crossing];
+        let (ahead, _) = follower.car_ahead(&cars, &config).expect("the overhanging car is ahead");
+        assert_eq!(ahead.id, 1);
+    }
+
+    #[test]
+    fn test_same_sector_same_tick() {
+        let mut state = State::default();
+        state.controller = Box::new(Blind);
+        let north = state.add_car_with(Direction::North, Turning::Straight, Model::Standard).unwrap();
+        let east = state.add_car_with(Direction::East, Turning::Straight, Model::Standard).unwrap();
+        let width = state.config.sector_width();
+        let step = state.config.max_velocity();
+        // Les deux voitures entrent dans le secteur (4, 4) pendant le prochain tick.
+        for car in state.roads.iter_mut().flat_map(|r| r.cars.iter_mut().flatten()) {
+            if car.id == north {
+                car.index = 3;
+                car.y = 3.0 * width - step / 2.0;
+            } else {
+                car.index = 6;
+                car.x = 5.0 * width + step / 2.0;
+            }
+        }
+        let cars = state.get_all_cars();
+        assert_eq!(cars[0].sector(1), cars[1].sector(1));
+
+        state.update();
+        let cars = state.get_all_cars();
+        let car = |id| cars.iter().find(|c| c.id == id).unwrap();
+        // La plus ancienne passe, l'autre s'arrête avant le secteur.
+        assert_eq!(car(north).index, 4);
+        assert_eq!((car(east).index, car(east).vel), (6, 0.0));
+        assert_eq!(state.stats.collisions(), 0);
+    }
+
     #[tokio::test]
     async fn test_simulation() {
         let state = common::setup().await;
@@ -60,10 +264,1286 @@ mod test_state {
 
         assert_eq!(state.stats.collisions(), 0);
     }
+
+    fn crossing_time(turning: Turning) -> (f32, u64) {
+        let mut state = State::default();
+        let tick = state.clock.tick();
+        let car = Car::new(Direction::North, turning, Model::Standard, 0, tick, &state.config);
+        state.roads[0].add_car(car);
+        while state.roads[0].cars.iter().any(|cars| !cars.is_empty()) {
+            state.update();
+        }
+        (state.stats.max_time(), state.clock.tick())
+    }
+
+    #[test]
+    fn test_clock_timing() {
+        let (time, ticks) = crossing_time(Turning::Straight);
+
+        // Identical input must give identical timings.
+        assert_eq!(crossing_time(Turning::Straight), (time, ticks));
+        assert!(time > 0.0);
+        assert!(ticks > 0);
+        // The car is removed at the start of the last update, before the clock advances.
+        assert_eq!(time, (ticks - 1) as f32 * Clock::dt());
+
+        // A left turn has a longer path than a straight crossing.
+        assert!(crossing_time(Turning::Left).0 > time);
+    }
+
+    fn run_seeded(seed: u64) -> State {
+        let mut state = State::with_seed(seed);
+        for _ in 0..20 {
+            state.add_car_random();
+            for _ in 0..60 {
+                state.update();
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn test_seed_reproducible() {
+        let a = run_seeded(7);
+        let b = run_seeded(7);
+
+        assert_eq!(a.seed, 7);
+        assert_eq!(a.total_cars, b.total_cars);
+        assert_eq!(a.stats, b.stats);
+        assert_eq!(a.roads, b.roads);
+    }
 }
+mod test_right_turn {
+    use crate::common;
+    use smart_road::circulation::*;
+    use smart_road::config::FPS;
+
+    #[test]
+    fn test_right_lane_visible() {
+        let mut state = State::default();
+        let tick = state.clock.tick();
+        let car = Car::new(Direction::North, Turning::Right, Model::Standard, 0, tick, &state.config);
+        state.roads[0].add_car(car);
+
+        // Visible dès sa création et jusqu'au bout de son `Path`.
+        while !common::cars_on_roads(&state).is_empty() {
+            assert_eq!(state.get_all_cars().len(), 1);
+            state.update();
+        }
+    }
+
+    /// Des voitures sur toutes les voies à la fois : celles qui tournent à
+    /// droite ne chevauchent jamais les autres, quel que soit le contrôleur.
+    #[test]
+    fn test_right_turners_never_overlap() {
+        let directions = [Direction::North, Direction::East, Direction::South, Direction::West];
+        for controller in ControllerKind::ALL {
+            let mut state = common::seeded(controller);
+            let mut right_turns = 0;
+            while state.clock.tick() < 20 * FPS {
+                if state.clock.tick() < 10 * FPS && state.clock.tick().is_multiple_of(FPS) {
+                    for direction in &directions {
+                        for turning in [Turning::Left, Turning::Straight, Turning::Right] {
+                            let _ = state.add_car_with(direction.clone(), turning, Model::Standard);
+                        }
+                    }
+                }
+                state.update();
+
+                let cars = common::cars_on_roads(&state);
+                for car in cars.iter().filter(|c| c.turning == Turning::Right) {
+                    right_turns += 1;
+                    for other in cars.iter().filter(|c| c.id != car.id) {
+                        assert!(
+                            !common::overlapping(car, other),
+                            "{} car {} overlaps car {} at tick {}",
+                            state.controller.name(),
+                            car.id,
+                            other.id,
+                            state.clock.tick()
+                        );
+                    }
+                }
+            }
+            assert!(right_turns > 0);
+        }
+    }
+}
+
+mod test_footprint {
+    use smart_road::circulation::*;
+    use smart_road::config::SimulationConfig;
+
+    #[test]
+    fn models_have_their_own_footprint() {
+        assert_ne!(Model::Sport.footprint(), Model::Standard.footprint());
+        assert_ne!(Model::Sport.footprint(), Model::TaxiVert.footprint());
+        for model in [Model::Standard, Model::Sport, Model::TaxiVert] {
+            let footprint = model.footprint();
+            assert!(footprint.width < footprint.length && footprint.length < 1.0);
+        }
+    }
+
+    #[test]
+    fn rect_follows_moving() {
+        let config = SimulationConfig::default();
+        let width = config.sector_width();
+        let footprint = Model::Standard.footprint();
+        let mut car = Car::new(Direction::West, Turning::Straight, Model::Standard, 0, 0, &config);
+
+        let horizontal = car.rects()[0].corners();
+        let span = |corners: [(f32, f32); 4], axis: fn(&(f32, f32)) -> f32| {
+            let values: Vec<f32> = corners.iter().map(axis).collect();
+            values.iter().cloned().fold(f32::MIN, f32::max) - values.iter().cloned().fold(f32::MAX, f32::min)
+        };
+        assert!((span(horizontal, |c| c.0) - footprint.length * width).abs() < 1e-3);
+        assert!((span(horizontal, |c| c.1) - footprint.width * width).abs() < 1e-3);
+
+        car.moving = Moving::Up;
+        let vertical = car.rects()[0].corners();
+        assert!((span(vertical, |c| c.0) - footprint.width * width).abs() < 1e-3);
+        assert!((span(vertical, |c| c.1) - footprint.length * width).abs() < 1e-3);
+    }
+
+    #[test]
+    fn overlap_and_gap() {
+        let a = OrientedRect::new((0.0, 0.0), (1.0, 0.0), 40.0, 20.0);
+
+        // Bout à bout, puis côte à côte.
+        let behind = OrientedRect::new((50.0, 0.0), (1.0, 0.0), 40.0, 20.0);
+        assert!(!a.overlaps(&behind));
+        assert!((a.gap(&behind) - 10.0).abs() < 1e-3);
+        let beside = OrientedRect::new((0.0, 25.0), (-1.0, 0.0), 40.0, 20.0);
+        assert!((a.gap(&beside) - 5.0).abs() < 1e-3);
+
+        // Perpendiculaire : les centres sont à 25 px, mais les rectangles se
+        // chevauchent, ce qu'une distance entre centres ne distingue pas.
+        let crossing = OrientedRect::new((25.0, 0.0), (0.0, 1.0), 40.0, 20.0);
+        assert!(a.overlaps(&crossing));
+        assert_eq!(a.gap(&crossing), 0.0);
+        let clear = OrientedRect::new((0.0, 35.0), (0.0, 1.0), 40.0, 20.0);
+        assert!(!a.overlaps(&clear));
+        assert!((a.gap(&clear) - 5.0).abs() < 1e-3);
+
+        // En diagonale, l'écart se mesure entre les coins.
+        let corner = OrientedRect::new((50.0, 25.0), (1.0, 0.0), 40.0, 20.0);
+        assert!((a.gap(&corner) - (10.0f32 * 10.0 + 5.0 * 5.0).sqrt()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn gaps_are_validated() {
+        let mut config = SimulationConfig::default();
+        assert!(config.set("collision_gap", "0.5").is_err());
+        assert!(config.set("collision_gap", "-0.1").is_err());
+        assert!(config.set("close_call_gap", "0.3").is_ok());
+    }
+}
+
+mod test_profile {
+    use smart_road::circulation::*;
+    use smart_road::config::{SimulationConfig, FPS};
+
+    fn car(model: Model, config: &SimulationConfig) -> Car {
+        Car::new(Direction::West, Turning::Straight, model, 0, 0, config)
+    }
+
+    /// Vitesse atteinte depuis l'arrêt après `seconds` secondes sur route dégagée.
+    fn speed_after(model: Model, seconds: f32, config: &SimulationConfig) -> f32 {
+        let mut car = car(model, config);
+        car.stop();
+        for _ in 0..(seconds * FPS as f32) as usize {
+            car.accelerate(config.window_size as f32, config);
+        }
+        car.vel
+    }
+
+    /// Distance parcourue, en pixels, en freinant au maximum depuis `vel`.
+    fn braking_distance(model: Model, vel: f32, config: &SimulationConfig) -> f32 {
+        let mut car = car(model, config);
+        car.vel = vel;
+        let mut distance = 0.0;
+        while car.vel > 0.0 {
+            car.brake(0.0, config);
+            distance += car.vel * config.max_velocity();
+        }
+        distance
+    }
+
+    #[test]
+    fn models_accelerate_differently() {
+        let config = SimulationConfig::default();
+        let sport = speed_after(Model::Sport, 0.5, &config);
+        let standard = speed_after(Model::Standard, 0.5, &config);
+        let taxi = speed_after(Model::TaxiVert, 0.5, &config);
+        assert!(sport > standard && standard > taxi, "{sport} {standard} {taxi}");
+    }
+
+    #[test]
+    fn top_speed_is_respected() {
+        let config = SimulationConfig::default();
+        for model in [Model::Standard, Model::Sport, Model::TaxiVert] {
+            let top = model.profile().max_speed * config.speed_limit;
+            assert!(speed_after(model.clone(), 20.0, &config) <= top);
+            assert!(speed_after(model, 20.0, &config) > top * 0.95);
+        }
+        assert!(speed_after(Model::Sport, 20.0, &config) > speed_after(Model::Standard, 20.0, &config));
+    }
+
+    #[test]
+    fn reaction_time_delays_start() {
+        let config = SimulationConfig::default();
+        let mut taxi = car(Model::TaxiVert, &config);
+        taxi.stop();
+        for _ in 0..Model::TaxiVert.profile().reaction_ticks() {
+            taxi.accelerate(config.window_size as f32, &config);
+            assert_eq!(taxi.vel, 0.0);
+        }
+        taxi.accelerate(config.window_size as f32, &config);
+        assert!(taxi.vel > 0.0);
+    }
+
+    #[test]
+    fn acceleration_is_in_sector_widths_per_second_squared() {
+        let config = SimulationConfig::default();
+        for model in [Model::Standard, Model::Sport, Model::GarbageTruck] {
+            let mut car = car(model.clone(), &config);
+            car.vel = 0.0;
+            car.accelerate(config.window_size as f32, &config);
+            // Gain de vitesse en un tick, en largeurs de secteur par seconde.
+            let gain = car.vel * config.speed_limit * FPS as f32;
+            assert!((gain - model.profile().max_acceleration).abs() < 1e-3, "{gain}");
+        }
+    }
+
+    #[test]
+    fn deceleration_is_limited() {
+        let config = SimulationConfig::default();
+        let mut standard = car(Model::Standard, &config);
+        standard.vel = config.speed_limit;
+        let max_drop = Model::Standard.profile().braking_per_tick(&config);
+        standard.brake(config.scan_distance_px() * 0.3, &config);
+        assert!((config.speed_limit - standard.vel - max_drop).abs() < 1e-5);
+    }
+
+    #[test]
+    fn stopping_at_the_line_respects_deceleration() {
+        let config = SimulationConfig::default();
+        for model in [Model::GarbageTruck, Model::Standard] {
+            let mut car = car(model.clone(), &config);
+            car.vel = car.top_speed(&config);
+            let max_drop = model.profile().braking_per_tick(&config);
+            let entry = car.path.entry_index();
+            loop {
+                let before = car.vel;
+                let stopped = car.stop_before(entry, &config);
+                assert!(before - car.vel <= max_drop + 1e-5, "{model:?}: {before} -> {}", car.vel);
+                if stopped {
+                    break;
+                }
+                car.advance(&[], &config);
+            }
+            assert!(car.index < entry, "{model:?} crossed the line");
+        }
+    }
+
+    #[test]
+    fn late_stops_are_capped_by_deceleration() {
+        let config = SimulationConfig::default();
+        for model in [Model::GarbageTruck, Model::Standard] {
+            let mut car = car(model.clone(), &config);
+            car.vel = car.top_speed(&config);
+            let entry = car.path.entry_index();
+            // Lancée à pleine vitesse jusqu'à un secteur de la ligne.
+            while car.distance_to(entry - 1) > config.sector_width() {
+                car.advance(&[], &config);
+            }
+            let before = car.vel;
+            car.stop_before(entry, &config);
+            let max_drop = model.profile().braking_per_tick(&config);
+            assert!((before - car.vel - max_drop).abs() < 1e-5, "{model:?}: {before} -> {}", car.vel);
+        }
+    }
+
+    #[test]
+    fn trucks_brake_longer() {
+        let config = SimulationConfig::default();
+        let vel = Model::GarbageTruck.profile().top_speed(&config);
+        let sport = braking_distance(Model::Sport, vel, &config);
+        let truck = braking_distance(Model::GarbageTruck, vel, &config);
+        assert!(truck > 3.0 * sport, "{truck} {sport}");
+
+        // v² / 2a, la vitesse en largeurs de secteur par seconde.
+        let speed = vel * config.speed_limit;
+        let expected = speed * speed / (2.0 * Model::GarbageTruck.profile().max_deceleration) * config.sector_width();
+        assert!((truck - expected).abs() < 0.2 * expected, "{truck} {expected}");
+    }
+}
+
+mod test_controller {
+    use crate::common;
+    use smart_road::circulation::*;
+    use smart_road::config::{SimulationConfig, FPS};
+
+    /// Ne laisse avancer aucune voiture.
+    #[derive(Debug)]
+    struct RedEverywhere;
+
+    impl IntersectionController for RedEverywhere {
+        fn name(&self) -> &'static str {
+            "red"
+        }
+
+        fn may_advance(&self, _car: &Car, _cars: &[Car], _ctx: &TickContext) -> bool {
+            false
+        }
+
+        fn drive(&mut self, _car: &mut Car, _cars: &[Car], _ctx: &TickContext) {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_default_controller() {
+        let state = State::default();
+        assert_eq!(state.controller.name(), "reactive");
+
+        let (config, _) =
+            SimulationConfig::from_args(["--controller".to_owned(), "reactive".to_owned()])
+                .unwrap();
+        assert_eq!(config.controller, ControllerKind::Reactive);
+    }
+
+    /// Le seul test d'endurance commun à tous les contrôleurs : deux minutes de
+    /// trafic aléatoire, camions-bennes compris, avec une ambulance environ
+    /// toutes les dix secondes.
+    #[test]
+    fn test_no_collisions() {
+        for controller in ControllerKind::ALL {
+            let mut state = common::seeded(controller);
+            let mut next = 10 * FPS;
+            let min = common::run_with(&mut state, 120, |state| {
+                state.add_car_random();
+                if state.clock.tick() >= next {
+                    let _ = state.add_emergency_random();
+                    next += 10 * FPS;
+                }
+            });
+
+            assert!(min > state.config.collision_gap_px(), "{controller:?}: min gap {min}");
+            assert_eq!(state.stats.collisions(), 0, "{controller:?}");
+            assert!(state.stats.max_time() > 0.0, "{controller:?}");
+            let trucks = state.stats.trips().iter().filter(|t| t.model == Model::GarbageTruck).count();
+            assert!(trucks > 0, "{controller:?}");
+            let delays = state.stats.emergency_delays();
+            assert!(delays.count() > 0, "{controller:?}");
+            assert!(delays.max() < 10.0, "{controller:?}: {}", delays.max());
+        }
+    }
+
+    #[test]
+    fn test_custom_controller() {
+        let mut state = State::default();
+        state.controller = Box::new(RedEverywhere);
+        state.add_car(Direction::North);
+        let start = state.roads[0].cars.iter().flatten().next().unwrap().clone();
+
+        for _ in 0..100 {
+            state.update();
+        }
+
+        let car = state.roads[0].cars.iter().flatten().next().unwrap();
+        assert_eq!((car.x, car.y, car.vel), (start.x, start.y, 0.0));
+    }
+}
+
+mod test_reservation {
+    use crate::common;
+    use smart_road::circulation::*;
+
+    #[test]
+    fn test_rejections() {
+        let mut state = common::seeded(ControllerKind::Reservation);
+        assert_eq!(state.controller.name(), "reservation");
+        // Simule seconde par seconde jusqu'au premier refus.
+        for seconds in 1..=60 {
+            common::run_random(&mut state, seconds);
+            if state.stats.reservation_rejections() > 0 {
+                return;
+            }
+        }
+        panic!("no reservation was rejected in a minute");
+    }
+
+    #[test]
+    fn granted_cars_ramp_up() {
+        let mut state = common::seeded(ControllerKind::Reservation);
+        let id = state.add_car_with(Direction::North, Turning::Straight, Model::GarbageTruck).unwrap();
+        let max_gain = Model::GarbageTruck.profile().acceleration_per_tick(&state.config);
+        let mut previous = None;
+        while let Some(car) = common::cars_on_roads(&state).into_iter().find(|c| c.id == id) {
+            if let Some(vel) = previous {
+                assert!(car.vel - vel <= max_gain + 1e-5, "{vel} -> {}", car.vel);
+            }
+            previous = Some(car.vel);
+            state.update();
+        }
+        assert_eq!(state.stats.trips().len(), 1);
+    }
+}
+
+mod test_traffic_lights {
+    use crate::common;
+    use smart_road::circulation::*;
+    use smart_road::config::{ConfigError, SimulationConfig, FPS};
+
+    #[test]
+    fn test_phase_plans() {
+        for preset in [PhasePreset::Split, PhasePreset::ProtectedLeft] {
+            let plan = SignalPlan {
+                preset,
+                ..SignalPlan::default()
+            };
+            assert_eq!(plan.validate(), Ok(()));
+        }
+
+        let phase = |direction: &str, duration: f32| {
+            format!(
+                "[[signals.phases]]\n\
+                 green = [{{ direction = \"{direction}\", turning = \"left\" }}, {{ direction = \"{direction}\", turning = \"straight\" }}]\n\
+                 duration = {duration}\n"
+            )
+        };
+        let plan = |durations: [f32; 4]| {
+            let phases = ["north", "east", "south", "west"].iter().zip(durations);
+            SimulationConfig::from_toml(&phases.map(|(d, t)| phase(d, t)).collect::<String>())
+        };
+        assert_eq!(plan([8.0, 4.0, 8.0, 4.0]).unwrap().signals.phases().len(), 4);
+        // L'approche est n'a jamais le vert.
+        assert!(matches!(plan([8.0, 0.0, 8.0, 4.0]), Err(ConfigError::Invalid(_))));
+        let through = SimulationConfig::from_toml(
+            "[[signals.phases]]\n\
+             green = [{ direction = \"north\", turning = \"straight\" }, { direction = \"south\", turning = \"straight\" }]",
+        );
+        assert!(matches!(through, Err(ConfigError::Invalid(_))));
+
+        let conflicting = SimulationConfig::from_toml(
+            "[[signals.phases]]\n\
+             green = [{ direction = \"north\", turning = \"left\" }, { direction = \"south\", turning = \"straight\" }]",
+        );
+        assert!(matches!(conflicting, Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_red_light_holds_car() {
+        // Le cycle commence par l'approche est, avec un vert plus long que le test.
+        let mut config = SimulationConfig {
+            controller: ControllerKind::TrafficLights,
+            ..SimulationConfig::default()
+        };
+        config.set("signals.green", "60").unwrap();
+        config.signals.phases = config.signals.phases();
+        config.signals.phases.rotate_left(1);
+        let mut state = State::new(config);
+        assert_eq!(state.controller.name(), "traffic_lights");
+        let tick = state.clock.tick();
+        let car = Car::new(Direction::North, Turning::Straight, Model::Standard, 0, tick, &state.config);
+        state.roads[0].add_car(car);
+
+        for _ in 0..10 * FPS {
+            state.update();
+        }
+        let car = &common::cars_on_roads(&state)[0];
+        assert_eq!(state.controller.signal(&Direction::North, &Turning::Straight), Some(Signal::Red));
+        assert_eq!(car.vel, 0.0);
+        assert!(car.progress() <= 2.0 * car.sector_width());
+        assert!(car.progress() > car.sector_width());
+    }
+}
+
+mod test_stop_sign {
+    use crate::common;
+    use smart_road::circulation::*;
+
+    fn spawn(state: &mut State, road: usize, direction: Direction) {
+        let tick = state.clock.tick();
+        let id = state.total_cars;
+        let car = Car::new(direction, Turning::Straight, Model::Standard, id, tick, &state.config);
+        state.roads[road].add_car(car);
+        state.total_cars += 1;
+    }
+
+    #[test]
+    fn test_full_stop() {
+        let mut state = common::seeded(ControllerKind::StopSign);
+        assert_eq!(state.controller.name(), "stop_sign");
+        spawn(&mut state, 0, Direction::North);
+
+        let mut stopped_at = None;
+        while state.roads[0].cars.iter().any(|cars| !cars.is_empty()) {
+            state.update();
+            if let Some(car) = common::cars_on_roads(&state).first() {
+                if car.vel == 0.0 && stopped_at.is_none() {
+                    stopped_at = Some(car.progress());
+                }
+            }
+        }
+        let width = state.config.sector_width();
+        let stopped_at = stopped_at.expect("the car never stopped");
+        assert!(stopped_at > width && stopped_at <= 2.0 * width);
+        assert!(state.stats.max_time() > 0.0);
+    }
+
+    #[test]
+    fn test_right_of_way() {
+        // Arrivées simultanées : la voiture venant de l'est a le nord à sa droite.
+        let mut state = common::seeded(ControllerKind::StopSign);
+        spawn(&mut state, 1, Direction::East);
+        spawn(&mut state, 0, Direction::North);
+
+        let first = loop {
+            state.update();
+            if let Some(car) = common::cars_on_roads(&state).iter().find(|c| c.in_intersection()) {
+                break car.direction.clone();
+            }
+        };
+        assert_eq!(first, Direction::North);
+    }
+}
+
+mod test_emergency {
+    use crate::common;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use smart_road::circulation::*;
+    use smart_road::config::FPS;
+
+    #[test]
+    fn conflicting_cars_yield() {
+        for controller in ControllerKind::ALL {
+            let mut state = common::seeded(controller);
+            let ambulance = Car::new(Direction::North, Turning::Straight, Model::Ambulance, 0, 0, &state.config);
+            let car = Car::new(Direction::East, Turning::Straight, Model::Standard, 1, 0, &state.config);
+            state.roads[0].add_car(ambulance);
+            state.roads[1].add_car(car);
+
+            while !common::cars_on_roads(&state).is_empty() && state.clock.tick() < 60 * FPS {
+                state.update();
+                let cars = common::cars_on_roads(&state);
+                let ambulance = cars.iter().find(|c| c.id == 0);
+                let car = cars.iter().find(|c| c.id == 1);
+                if let (Some(ambulance), Some(car)) = (ambulance, car) {
+                    let exit = (ambulance.path.exit_index() + 1) as f32 * ambulance.sector_width();
+                    if ambulance.progress() < exit {
+                        assert!(!car.in_intersection(), "{controller:?}");
+                    }
+                }
+            }
+
+            let trips = state.stats.trips();
+            assert_eq!(trips.len(), 2, "{controller:?}");
+            let ambulance = trips.iter().find(|t| t.model == Model::Ambulance).unwrap();
+            assert_eq!(ambulance.stops, 0, "{controller:?}");
+            assert_eq!(state.stats.emergency_delays().count(), 1);
+        }
+    }
+
+    #[test]
+    fn ambulances_do_not_reserve() {
+        let mut state = common::seeded(ControllerKind::Reservation);
+        let ambulance = state.add_car_with(Direction::North, Turning::Straight, Model::Ambulance).unwrap();
+        let mut follower = None;
+        while state.clock.tick() < 30 * FPS {
+            state.update();
+            if follower.is_none() {
+                follower = state.add_car_with(Direction::North, Turning::Straight, Model::Standard).ok();
+            }
+            let Some(ControllerSnapshot::Reservation(manager)) = state.controller.snapshot() else {
+                unreachable!()
+            };
+            let cars = state.get_all_cars();
+            let car = |id| cars.iter().find(|c| c.id == id);
+            if let Some(ambulance) = car(ambulance) {
+                assert!(!manager.is_granted(ambulance), "tick {}", state.clock.tick());
+                // La voiture qui le suit ne réserve qu'une fois l'intersection dégagée.
+                if let Some(follower) = follower.and_then(car) {
+                    let cleared = ambulance.cleared_intersection();
+                    assert!(cleared || !manager.is_granted(follower), "tick {}", state.clock.tick());
+                }
+            }
+        }
+        assert_eq!(state.stats.trips().len(), 2);
+    }
+
+    #[test]
+    fn ambulances_need_a_free_lane() {
+        let mut state = State::default();
+        for turning in [Turning::Left, Turning::Straight, Turning::Right] {
+            state.add_car_with(Direction::North, turning, Model::Standard).unwrap();
+        }
+        assert_eq!(state.add_emergency(Direction::North), Err(SpawnError::LaneOccupied));
+        assert_eq!(state.add_emergency(Direction::South), Ok(3));
+    }
+
+    #[test]
+    fn ambulances_are_never_random() {
+        let mut rng = ChaCha8Rng::seed_from_u64(common::SEED);
+        assert!((0..1000).all(|_| !Model::random(&mut rng).is_emergency()));
+    }
+}
+
+mod test_long_vehicles {
+    use crate::common;
+    use smart_road::circulation::*;
+    use smart_road::config::{SimulationConfig, FPS};
+
+    #[test]
+    fn trucks_span_two_sectors() {
+        let config = SimulationConfig::default();
+        let mut state = State::new(config.clone());
+        let truck = Car::new(Direction::South, Turning::Left, Model::GarbageTruck, 0, 0, &config);
+        assert_eq!(truck.span(), 2);
+        state.roads[2].add_car(truck);
+
+        let mut bent = false;
+        while let Some(truck) = common::cars_on_roads(&state).first() {
+            let occupied = truck.occupied_sectors();
+            assert_eq!(occupied.len(), truck.index.min(1) + 1);
+            assert_eq!(occupied.last(), Some(&truck.sector(0)));
+            let cells = truck.cells();
+            assert_eq!(cells.len(), 2);
+            bent |= cells[0].1 != cells[1].1;
+            state.update();
+        }
+        assert!(bent, "the tail never followed the turn");
+        assert_eq!(state.stats.trips().len(), 1);
+    }
+
+    #[test]
+    fn followers_keep_clear_of_the_tail() {
+        for controller in ControllerKind::ALL {
+            let mut state = common::seeded(controller);
+            let truck = Car::new(Direction::North, Turning::Straight, Model::GarbageTruck, 0, 0, &state.config);
+            state.roads[0].add_car(truck);
+            state.total_cars = 1;
+
+            while state.roads[0].cars[1].len() < 2 {
+                // La voie n'est libre qu'une fois l'arrière du camion passé.
+                let truck = &state.roads[0].cars[1][0];
+                let free = truck.index + 1 >= truck.span() + 3;
+                if state.queued() == 0 {
+                    state.add_car(Direction::North);
+                }
+                assert!(free || state.roads[0].cars[1].len() == 1, "{controller:?}");
+                state.update();
+            }
+
+            while state.clock.tick() < 30 * FPS {
+                state.update();
+                let lane = &state.roads[0].cars[1];
+                if let [truck, follower, ..] = lane.as_slice() {
+                    assert!(truck.model == Model::GarbageTruck);
+                    assert!(!truck.occupies(&follower.sector(0)), "{controller:?}");
+                    assert!(!truck.overlaps(follower), "{controller:?}");
+                }
+            }
+        }
+    }
+
+}
+
+mod test_scenario {
+    use std::fs;
+
+    use smart_road::circulation::*;
+    use smart_road::config::{SimulationConfig, FPS};
+
+    #[test]
+    fn parse_scenarios() {
+        let toml = "[[spawns]]\ntick = 30\ndirection = \"north\"\nturning = \"left\"\nvelocity = 0.5\n";
+        let scenario = Scenario::from_toml(toml).unwrap();
+        let spawn = &scenario.spawns[0];
+        assert_eq!((spawn.tick, spawn.model.clone(), spawn.velocity), (30, Model::Standard, Some(0.5)));
+
+        let json = r#"{"spawns": [{"tick": 30, "direction": "north", "turning": "left", "velocity": 0.5}]}"#;
+        assert_eq!(Scenario::from_json(json).unwrap(), scenario);
+
+        assert!(Scenario::from_toml("[[spawns]]\ntick = 0\ndirection = \"north\"\nturning = \"left\"\nspeed = 1.0").is_err());
+        assert!(Scenario::from_toml("[[spawns]]\ntick = 0\ndirection = \"north\"\nturning = \"left\"\nvelocity = -1.0").is_err());
+        assert!(Scenario::from_toml("[[spawns]]\ntick = 0\ndirection = \"north\"\nturning = \"left\"\nvelocity = 0.0").is_ok());
+    }
+
+    #[test]
+    fn spawns_on_schedule() {
+        let mut state = State::new(SimulationConfig::default());
+        let toml = "
+            [[spawns]]
+            tick = 10
+            direction = \"west\"
+            turning = \"straight\"
+            model = \"garbage_truck\"
+            velocity = 0.5
+
+            [[spawns]]
+            tick = 10
+            direction = \"west\"
+            turning = \"straight\"
+        ";
+        state.load_scenario(&Scenario::from_toml(toml).unwrap());
+
+        while state.clock.tick() <= 10 {
+            assert!(state.get_all_cars().is_empty());
+            state.update();
+        }
+        let cars = state.get_all_cars();
+        assert_eq!(cars.len(), 1);
+        assert_eq!(cars[0].model, Model::GarbageTruck);
+        assert!((cars[0].vel - 0.5).abs() < 0.05);
+        assert!(!state.scenario_finished());
+
+        // La deuxième voiture attend que sa voie soit libre.
+        while !state.scenario_finished() {
+            state.update();
+            assert!(state.clock.tick() < 10 * FPS);
+        }
+        let lane = &state.roads[3].cars[1];
+        assert_eq!(lane.len(), 2);
+        assert!(lane[0].index + 1 >= lane[0].span() + 3);
+    }
+
+    /// Les apparitions programmées passent par les files d'attente : elles
+    /// comptent dans les temps d'attente et attendent que le trafic ne soit
+    /// plus bloqué, mais elles ne sont jamais perdues.
+    #[test]
+    fn scheduled_spawns_are_queued() {
+        let mut state = State::new(SimulationConfig {
+            queue_capacity: 1,
+            ..SimulationConfig::default()
+        });
+        let spawn = |direction: &str| {
+            format!("[[spawns]]\ntick = 0\ndirection = \"{direction}\"\nturning = \"straight\"\n")
+        };
+        let toml = spawn("north").repeat(3);
+        state.load_scenario(&Scenario::from_toml(&toml).unwrap());
+        state.update();
+        assert_eq!(state.get_all_cars().len(), 1);
+        assert_eq!((state.queued(), state.stats.dropped()), (2, 0));
+        while !state.scenario_finished() {
+            state.update();
+            assert!(state.clock.tick() < 20 * FPS);
+        }
+        assert_eq!(state.stats.queue_waits().count(), 3);
+        assert!(state.stats.queue_waits().max() > 0.0);
+
+        // Trafic bloqué : la voiture programmée attend dans sa file.
+        let mut state = State::default();
+        for direction in [Direction::North, Direction::East] {
+            for turning in [Turning::Left, Turning::Straight, Turning::Right] {
+                state.add_car_with(direction.clone(), turning, Model::Standard).unwrap();
+            }
+        }
+        state.roads.iter_mut().flat_map(|r| r.cars.iter_mut().flatten()).for_each(|c| c.stop());
+        state.load_scenario(&Scenario::from_toml(&spawn("west")).unwrap());
+        state.update();
+        assert_eq!((state.get_all_cars().len(), state.queue_len(&Direction::West)), (6, 1));
+        assert!(!state.scenario_finished());
+    }
+
+    /// Quatre virages à gauche arrivés au même tick : les plus anciens passent
+    /// d'abord, au lieu de s'engager tous au centre et de s'y bloquer.
+    #[test]
+    fn same_tick_left_turns() {
+        let scenario = Scenario::load("scenarios/four_left_turns.toml").unwrap();
+        let mut state = State::new(SimulationConfig::default());
+        state.load_scenario(&scenario);
+        while !state.scenario_finished() || !state.get_all_cars().is_empty() {
+            state.update();
+            assert!(state.clock.tick() < 30 * FPS, "stuck");
+        }
+        assert_eq!(state.stats.trips().len(), 4);
+        assert_eq!(state.stats.collisions(), 0);
+    }
+
+    /// Chaque scénario de `scenarios/` se déroule sans collision avec tous les
+    /// contrôleurs, et toutes ses voitures quittent la fenêtre.
+    #[test]
+    fn regression_scenarios() {
+        let mut paths: Vec<_> = fs::read_dir("scenarios").unwrap().map(|e| e.unwrap().path()).collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let scenario = Scenario::load(&path).unwrap();
+            for controller in ControllerKind::ALL {
+                let mut state = State::new(SimulationConfig {
+                    controller,
+                    ..SimulationConfig::default()
+                });
+                state.load_scenario(&scenario);
+                while !state.scenario_finished() || !state.get_all_cars().is_empty() {
+                    state.update();
+                    assert!(state.clock.tick() < 120 * FPS, "{}: {controller:?} is stuck", path.display());
+                }
+                assert_eq!(state.stats.trips().len(), scenario.spawns.len(), "{}: {controller:?}", path.display());
+                assert_eq!(state.stats.collisions(), 0, "{}: {controller:?}", path.display());
+            }
+        }
+    }
+}
+
+mod test_demand {
+    use rand::rngs::mock::StepRng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use smart_road::circulation::*;
+    use smart_road::config::{ConfigError, SimulationConfig, FPS};
+
+    #[test]
+    fn poisson_rates_and_split() {
+        let demand = Demand {
+            enabled: true,
+            rates: PerApproach { north: 30.0, east: 0.0, south: 6.0, west: 0.0 },
+            turning: PerApproach::uniform(TurningSplit { left: 1.0, straight: 3.0, right: 0.0 }),
+            profile: Vec::new(),
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut arrivals = Vec::new();
+        // Une heure simulée.
+        for tick in 0..3600 * FPS {
+            arrivals.extend(demand.arrivals(tick as f32 / FPS as f32, Clock::dt(), &mut rng));
+        }
+        let count = |d: Direction| arrivals.iter().filter(|(dir, _)| *dir == d).count() as f32;
+        assert!((count(Direction::North) - 1800.0).abs() < 150.0, "{}", count(Direction::North));
+        assert!((count(Direction::South) - 360.0).abs() < 60.0, "{}", count(Direction::South));
+        assert_eq!(count(Direction::East) + count(Direction::West), 0.0);
+
+        let left = arrivals.iter().filter(|(_, t)| *t == Turning::Left).count() as f32;
+        assert!((left / arrivals.len() as f32 - 0.25).abs() < 0.03);
+        assert!(arrivals.iter().all(|(_, t)| *t != Turning::Right));
+    }
+
+    #[test]
+    fn zero_weights_are_never_sampled() {
+        let splits = [
+            TurningSplit { left: 1.0, straight: 2.0, right: 0.0 },
+            TurningSplit { left: 0.0, straight: 0.3, right: 0.7 },
+            TurningSplit { left: 0.1, straight: 0.0, right: 0.0 },
+        ];
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for split in splits {
+            let allowed = |t: &Turning| match t {
+                Turning::Left => split.left > 0.0,
+                Turning::Straight => split.straight > 0.0,
+                Turning::Right => split.right > 0.0,
+            };
+            // Les tirages extrêmes, 0 et juste sous 1, puis des tirages au hasard.
+            assert!(allowed(&split.sample(&mut StepRng::new(0, 0))), "{split:?}");
+            assert!(allowed(&split.sample(&mut StepRng::new(u64::MAX, 0))), "{split:?}");
+            assert!((0..10_000).all(|_| allowed(&split.sample(&mut rng))), "{split:?}");
+        }
+
+        let turning = |north: &str| {
+            let other = "{ left = 1.0, straight = 1.0, right = 1.0 }";
+            format!("[demand.turning]\nnorth = {north}\neast = {other}\nsouth = {other}\nwest = {other}")
+        };
+        assert!(SimulationConfig::from_toml(&turning("{ left = 1.0, right = 0.0 }")).is_ok());
+        let all_zero = SimulationConfig::from_toml(&turning("{ left = 0.0 }")).unwrap_err();
+        assert!(all_zero.to_string().contains("non-negative and not all zero"), "{all_zero}");
+    }
+
+    #[test]
+    fn profile_factor() {
+        let demand = Demand {
+            profile: vec![
+                ProfilePoint { time: 60.0, factor: 0.5 },
+                ProfilePoint { time: 120.0, factor: 2.0 },
+            ],
+            ..Demand::default()
+        };
+        assert_eq!(demand.factor(0.0), 0.5);
+        assert_eq!(demand.factor(90.0), 1.25);
+        assert_eq!(demand.factor(600.0), 2.0);
+        assert_eq!(demand.rate(&Direction::West, 90.0), 25.0);
+        assert_eq!(Demand::default().factor(42.0), 1.0);
+    }
+
+    #[test]
+    fn demand_config() {
+        let toml = "
+            [demand]
+            enabled = true
+            rates = { north = 30.0, east = 10.0, south = 30.0, west = 10.0 }
+            [demand.turning]
+            north = { straight = 1.0 }
+            east = { left = 1.0, right = 1.0 }
+            south = { straight = 1.0 }
+            west = { left = 1.0, right = 1.0 }
+            [[demand.profile]]
+            time = 0.0
+            factor = 1.0
+        ";
+        let mut config = SimulationConfig::from_toml(toml).unwrap();
+        assert_eq!(config.demand.rates.get(&Direction::East), &10.0);
+        assert_eq!(config.demand.turning.north.left, 0.0);
+
+        config.set("demand.rates.west", "12").unwrap();
+        assert_eq!(config.demand.rates.west, 12.0);
+        assert!(matches!(config.set("demand.rates.west", "-1"), Err(ConfigError::Invalid(_))));
+        assert!(SimulationConfig::from_toml("[demand.turning]\nnorth = { left = 0.0 }").is_err());
+        let decreasing = "[[demand.profile]]\ntime = 10.0\nfactor = 1.0\n[[demand.profile]]\ntime = 5.0\nfactor = 1.0";
+        assert!(SimulationConfig::from_toml(decreasing).is_err());
+    }
+
+    #[test]
+    fn state_follows_demand() {
+        let mut config = SimulationConfig {
+            seed: Some(3),
+            ..SimulationConfig::default()
+        };
+        config.demand.enabled = true;
+        config.demand.rates = PerApproach { north: 0.0, east: 12.0, south: 0.0, west: 0.0 };
+        config.demand.turning.east = TurningSplit { right: 1.0, ..TurningSplit::default() };
+        let mut state = State::new(config);
+        while state.clock.tick() < 120 * FPS {
+            state.add_demand();
+            state.update();
+        }
+        let trips = state.stats.trips();
+        assert!(trips.len() > 10, "{}", trips.len());
+        assert!(trips.iter().all(|t| t.direction == Direction::East && t.turning == Turning::Right));
+    }
+}
+
+mod test_queue {
+    use smart_road::circulation::*;
+    use smart_road::config::{SimulationConfig, FPS};
+
+    #[test]
+    fn cars_wait_for_a_free_lane() {
+        let mut state = State::default();
+        for _ in 0..5 {
+            state.add_car(Direction::North);
+        }
+        // Une voiture par voie, les deux autres attendent.
+        assert_eq!(state.roads[0].cars.iter().flatten().count(), 3);
+        assert_eq!(state.queue_len(&Direction::North), 2);
+        assert_eq!(state.queued(), 2);
+
+        while state.queued() > 0 {
+            state.update();
+            assert!(state.clock.tick() < 10 * FPS);
+        }
+        assert_eq!(state.total_cars, 5);
+        let waits = state.stats.queue_waits();
+        assert_eq!(waits.count(), 5);
+        assert!(waits.max() > 0.0);
+        assert_eq!(state.stats.queue_lengths().max(), 2.0);
+        assert_eq!(state.stats.dropped(), 0);
+    }
+
+    #[test]
+    fn full_queues_drop_demand() {
+        let mut state = State::new(SimulationConfig {
+            queue_capacity: 2,
+            ..SimulationConfig::default()
+        });
+        for _ in 0..10 {
+            state.add_car(Direction::East);
+        }
+        assert_eq!(state.total_cars, 3);
+        assert_eq!(state.queued(), 2);
+        assert_eq!(state.stats.dropped(), 5);
+        assert!(state.stats.summary().contains(&("Dropped", "5".to_owned())));
+    }
+}
+
+mod test_trips {
+    use crate::common;
+    use smart_road::circulation::*;
+
+    #[test]
+    fn test_trip_records() {
+        let mut state = State::with_seed(common::SEED);
+        common::run_random(&mut state, 60);
+        let trips = state.stats.trips();
+
+        assert!(!trips.is_empty());
+        assert_eq!(trips.len(), state.total_cars - common::cars_on_roads(&state).len());
+        for trip in trips {
+            let enter = trip.enter_tick.expect("every path crosses the intersection");
+            assert!(trip.spawn_tick < enter && enter < trip.exit_tick);
+            assert!(trip.min_vel <= trip.max_vel);
+            assert!(trip.time() > 0.0);
+        }
+        assert_eq!(trips.iter().map(|t| t.time()).fold(0.0, f32::max), state.stats.max_time());
+    }
+
+    #[test]
+    fn test_export() {
+        let mut state = State::with_seed(common::SEED);
+        common::run_random(&mut state, 30);
+        let trips = state.stats.trips();
+
+        let csv = trips_to_csv(trips);
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("id,direction,turning,model"));
+        assert_eq!(lines.count(), trips.len());
+
+        let parsed: Vec<TripRecord> = serde_json::from_str(&trips_to_json(trips)).unwrap();
+        assert_eq!(parsed, trips);
+    }
+}
+
+mod test_trace {
+    use std::env;
+
+    use crate::common;
+    use smart_road::circulation::*;
+    use smart_road::config::FPS;
+
+    fn record(name: &str, seconds: u64) -> (State, Trace) {
+        let path = env::temp_dir().join(format!("smart-road-{name}-{}.jsonl", std::process::id()));
+        let mut state = State::with_seed(common::SEED);
+        state.record_trace(&path).unwrap();
+        common::run_random(&mut state, seconds);
+        state.finish_trace().unwrap();
+        let trace = Trace::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        (state, trace)
+    }
+
+    #[test]
+    fn record_and_load() {
+        let (state, trace) = record("record", 30);
+        assert_eq!(trace.header.seed, common::SEED);
+        assert_eq!(trace.header.config, state.config);
+        assert_eq!(trace.frames.len() as u64, state.clock.tick());
+        assert!(trace.frames.iter().enumerate().all(|(i, f)| f.tick == i as u64));
+        assert_eq!(trace.frames.iter().map(|f| f.spawns.len()).sum::<usize>(), state.total_cars);
+        assert_eq!(trace.incidents, state.stats.incidents());
+
+        // La dernière ligne redonne les voitures de l'état final.
+        let mut cars = trace.cars(trace.frames.len() - 1);
+        let mut expected = state.get_all_cars();
+        cars.sort_by_key(|c| c.id);
+        expected.sort_by_key(|c| c.id);
+        assert_eq!(cars.len(), expected.len());
+        for (car, expected) in cars.iter().zip(&expected) {
+            assert_eq!((car.direction.clone(), car.turning.clone()), (expected.direction.clone(), expected.turning.clone()));
+            assert_eq!((car.x, car.y, car.index), (expected.x, expected.y, expected.index));
+            assert_eq!(car.cells(), expected.cells());
+        }
+
+        assert!(Trace::from_json_lines("").is_err());
+        assert!(Trace::from_json_lines("{}").is_err());
+    }
+
+    #[test]
+    fn replay_controls() {
+        let (_, trace) = record("replay", 5);
+        let mut replay = Replay::new(trace);
+        replay.advance(1.0);
+        assert_eq!(replay.tick(), FPS);
+
+        replay.scale_speed(2.0);
+        replay.advance(0.5);
+        assert_eq!(replay.tick(), 2 * FPS);
+
+        replay.paused = true;
+        replay.advance(1.0);
+        replay.seek_frames(-1.0);
+        assert_eq!(replay.tick(), 2 * FPS - 1);
+
+        replay.seek(-10.0);
+        assert_eq!(replay.frame(), 0);
+        replay.seek(10.0);
+        assert!(replay.finished());
+        assert_eq!(replay.cars().len(), replay.trace.frames.last().unwrap().cars.len());
+
+        replay.restart();
+        assert_eq!(replay.frame(), 0);
+    }
+}
+
+mod test_snapshot {
+    use crate::common;
+    use smart_road::circulation::*;
+
+    fn positions(state: &State) -> Vec<(usize, f32, f32, usize, f32)> {
+        let mut cars: Vec<_> = state.get_all_cars().iter().map(|c| (c.id, c.x, c.y, c.index, c.vel)).collect();
+        cars.sort_by_key(|c| c.0);
+        cars
+    }
+
+    /// Un état restauré, depuis JSON comme depuis bincode, continue exactement
+    /// comme l'original, générateur aléatoire compris.
+    #[test]
+    fn restored_state_continues_identically() {
+        for controller in ControllerKind::ALL {
+            let mut state = common::seeded(controller);
+            common::run_random(&mut state, 5);
+            let snapshot = state.snapshot();
+            assert_eq!(snapshot.tick(), state.clock.tick());
+
+            let from_json = Snapshot::from_json(&snapshot.to_json()).unwrap();
+            let from_bincode = Snapshot::from_bincode(&snapshot.to_bincode()).unwrap();
+            common::run_random(&mut state, 15);
+            for snapshot in [from_json, from_bincode] {
+                let mut restored = State::restore(snapshot);
+                assert_eq!(restored.controller.name(), state.controller.name());
+                common::run_random(&mut restored, 15);
+                assert_eq!(positions(&restored), positions(&state), "{controller:?}");
+                assert_eq!(restored.stats.summary(), state.stats.summary(), "{controller:?}");
+                assert_eq!(restored.stats.exited(), state.stats.exited(), "{controller:?}");
+                assert!(state.stats.trips().ends_with(restored.stats.trips()), "{controller:?}");
+                assert!(state.stats.incidents().ends_with(restored.stats.incidents()), "{controller:?}");
+                assert_eq!(restored.total_cars, state.total_cars);
+            }
+        }
+    }
+
+    /// Un instantané ne garde que les trajets récents et les incidents en
+    /// cours, sans fausser les compteurs.
+    #[test]
+    fn snapshots_trim_the_logs() {
+        let mut state = common::seeded(ControllerKind::Reactive);
+        common::run_random(&mut state, 20);
+        let exited = state.stats.exited();
+        assert!(exited > 0);
+
+        // Une collision terminée, puis un presque accident en cours.
+        let config = state.config.clone();
+        let car = |id: usize, offset: f32| {
+            let mut car = Car::new(Direction::West, Turning::Straight, Model::Standard, id, 0, &config);
+            car.x = offset * config.sector_width();
+            car
+        };
+        let tick = state.clock.tick();
+        state.incidents.update(&[car(100, 0.0), car(101, 0.5)], tick, &config, &mut state.stats);
+        state.incidents.update(&[car(100, 0.0), car(102, 0.85)], tick + 1, &config, &mut state.stats);
+        let last = state.stats.incidents().last().unwrap().clone();
+        assert_eq!((last.kind, last.cars), (IncidentKind::CloseCall, (100, 102)));
+
+        for _ in 0..TRIP_HISTORY {
+            state.clock.advance();
+        }
+        let restored = State::restore(Snapshot::from_json(&state.snapshot().to_json()).unwrap());
+        assert!(restored.stats.trips().is_empty());
+        assert_eq!(restored.stats.exited(), exited);
+        assert_eq!(restored.stats.incidents(), [last]);
+        assert!(restored.stats.collisions() > 0);
+        assert_eq!(restored.stats.summary(), state.stats.summary());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut state = State::with_seed(common::SEED);
+        common::run_random(&mut state, 10);
+        let dir = std::env::temp_dir();
+        for name in ["snapshot.json", "snapshot.bin"] {
+            let path = dir.join(format!("smart-road-{}-{name}", std::process::id()));
+            state.snapshot().save(&path).unwrap();
+            let restored = State::restore(Snapshot::load(&path).unwrap());
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(positions(&restored), positions(&state));
+            assert_eq!(restored.seed, state.seed);
+        }
+        assert!(matches!(Snapshot::from_json("{}"), Err(SnapshotError::Parse(_))));
+        assert!(matches!(Snapshot::load("missing.json"), Err(SnapshotError::Io(_))));
+    }
+
+    /// Un instantané modifié à la main est vérifié comme un fichier de
+    /// configuration, et l'état du contrôleur doit correspondre à la configuration.
+    #[test]
+    fn edited_snapshots_are_validated() {
+        let mut state = common::seeded(ControllerKind::Reservation);
+        state.add_car(Direction::North);
+        let json = state.snapshot().to_json();
+        let edit = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut fields: serde_json::Value = serde_json::from_str(&json).unwrap();
+            change(&mut fields);
+            Snapshot::from_json(&fields.to_string())
+        };
+        let config = |key: &'static str, value: serde_json::Value| {
+            edit(&move |fields| fields["config"][key] = value.clone())
+        };
+        assert!(config("seed", 7.into()).is_ok());
+        assert!(matches!(config("speed_limit", 0.into()), Err(SnapshotError::Parse(_))));
+        assert!(matches!(config("controller", "stop_sign".into()), Err(SnapshotError::Parse(_))));
+        assert!(matches!(config("controller", "reactive".into()), Err(SnapshotError::Parse(_))));
+
+        // Un incident en cours absent du journal.
+        let incident = edit(&|fields| fields["incidents"]["active"] = serde_json::json!([[[0, 1], 0]]));
+        assert!(matches!(incident, Err(SnapshotError::Parse(_))));
+        // Une voiture au-delà du dernier secteur de son chemin.
+        let car = edit(&|fields| {
+            let lanes = fields["roads"][0]["cars"].as_array_mut().unwrap();
+            let lane = lanes.iter_mut().find(|l| !l.as_array().unwrap().is_empty()).unwrap();
+            lane[0]["index"] = 1000.into();
+        });
+        assert!(matches!(car, Err(SnapshotError::Parse(_))));
+
+        let reactive = common::seeded(ControllerKind::Reactive).snapshot();
+        assert!(Snapshot::from_bincode(&reactive.to_bincode()).is_ok());
+    }
+}
+
+mod test_playback {
+    use smart_road::circulation::*;
+
+    #[test]
+    fn pause_step_and_time_scale() {
+        let mut playback = Playback::default();
+        assert_eq!(playback.ticks(), 1);
+
+        playback.scale(4.0);
+        assert_eq!(playback.ticks(), 4);
+        playback.scale(100.0);
+        assert_eq!(playback.time_scale(), 16.0);
+
+        // Au quart de la vitesse, un tick toutes les quatre images.
+        playback.scale(0.001);
+        assert_eq!(playback.time_scale(), 0.25);
+        assert_eq!((0..8).map(|_| playback.ticks()).collect::<Vec<_>>(), [0, 0, 0, 1, 0, 0, 0, 1]);
+
+        playback.step();
+        playback.paused = true;
+        assert_eq!(playback.ticks(), 0);
+        playback.step();
+        playback.step();
+        assert_eq!(playback.ticks(), 2);
+        assert_eq!(playback.ticks(), 0);
+    }
+}
+
 mod test_statistics {
+    use crate::common;
     use macroquad::rand::gen_range;
-    use smart_road::circulation::Statistics;
+    use smart_road::circulation::*;
+    use smart_road::config::{SimulationConfig, FPS};
+
+    #[test]
+    fn rolling_statistics() {
+        let mut state = State::with_seed(common::SEED);
+        common::run_random(&mut state, 90);
+        let (stats, now) = (&state.stats, state.clock.tick());
+        let trips = stats.trips();
+        assert_eq!(stats.max_vehicles(), state.total_cars);
+
+        // Sur toute la simulation, les valeurs glissantes sont les globales.
+        assert!((stats.throughput(now, now) - trips.len() as f32 / 1.5).abs() < 1e-3);
+        let mean = trips.iter().map(TripRecord::time).sum::<f32>() / trips.len() as f32;
+        assert!((stats.recent_mean_time(now, now) - mean).abs() < 1e-3);
+
+        let last_minute = trips.iter().filter(|t| t.exit_tick >= now - 60 * FPS).count();
+        assert_eq!(stats.throughput(now, 60 * FPS), last_minute as f32);
+        assert_eq!(stats.recent_mean_time(0, 60 * FPS), 0.0);
+
+        // Tranches complètes de 10 s : les 90 s se découpent en 9 tranches.
+        let history = stats.exit_history(now, 10 * FPS, 12);
+        assert_eq!(&history[..3], [0, 0, 0]);
+        assert_eq!(history.iter().sum::<u32>() as usize, trips.len());
+        let first = trips.iter().filter(|t| t.exit_tick < 10 * FPS).count();
+        assert_eq!(history[3] as usize, first);
+    }
 
     #[test]
     fn max_vehicles() {
@@ -134,14 +1614,91 @@ mod test_statistics {
         assert_eq!(stats.max_velocity(), n + 1.0);
     }
 
+    #[test]
+    fn average_time() {
+        let mut stats = Statistics::new();
+        for time in [1.0, 3.0, 5.0, 7.0, 9.0] {
+            stats.set_time(time);
+        }
+
+        assert_eq!(stats.average_time(), 5.0);
+        assert_eq!(stats.times().count(), 5);
+        assert_eq!(stats.times().variance(), 10.0);
+    }
+
+    #[test]
+    fn quantiles() {
+        let mut distribution = Distribution::new(0.5);
+        for i in 1..=100 {
+            distribution.add(i as f32);
+        }
+
+        assert_eq!(distribution.mean(), 50.5);
+        assert!((distribution.quantile(0.5) - 50.0).abs() <= 0.5);
+        assert!((distribution.quantile(0.9) - 90.0).abs() <= 0.5);
+        assert!((distribution.quantile(0.99) - 99.0).abs() <= 0.5);
+        assert_eq!(distribution.quantile(1.0), 100.0);
+        assert_eq!(Distribution::new(1.0).quantile(0.5), 0.0);
+    }
+
+    #[test]
+    fn histogram_is_bounded() {
+        let mut distribution = Distribution::new(0.05);
+        distribution.add(1.0);
+        distribution.add(100_000.0);
+
+        // Avec des classes de largeur fixe, 2 millions de classes.
+        assert!(serde_json::to_string(&distribution).unwrap().len() < 20_000);
+        assert!((distribution.quantile(0.5) - 1.0).abs() <= 0.05);
+        assert!((distribution.quantile(0.99) - 100_000.0).abs() <= 200.0);
+        assert_eq!(distribution.max(), 100_000.0);
+    }
+
+    /// Des traversées de plusieurs minutes, comme dans un carrefour saturé,
+    /// gardent des centiles distincts.
+    #[test]
+    fn long_times_keep_their_percentiles() {
+        let mut stats = Statistics::new();
+        for i in 1..=1000 {
+            stats.set_time(i as f32);
+        }
+        let times = stats.times();
+        assert!((times.quantile(0.5) - 500.0).abs() <= 1.0, "{}", times.quantile(0.5));
+        assert!((times.quantile(0.9) - 900.0).abs() <= 2.0, "{}", times.quantile(0.9));
+        assert!((times.quantile(0.99) - 990.0).abs() <= 2.0, "{}", times.quantile(0.99));
+        assert_eq!(times.quantile(1.0), 1000.0);
+    }
+
+    fn car_at(id: usize, x: f32) -> Car {
+        let config = SimulationConfig::default();
+        let mut car = Car::new(Direction::West, Turning::Straight, Model::Standard, id, 0, &config);
+        car.x = x;
+        car
+    }
+
     #[test]
     fn collisions() {
+        let config = SimulationConfig::default();
+        let width = config.sector_width();
         let mut stats = Statistics::new();
+        let mut tracker = IncidentTracker::new();
 
-        let n = gen_range(1, 100) as u32;
-        for _ in 0..=(n * 120) {
-            stats.set_collisions();
+        // Une paire se rapproche jusqu'à la collision puis s'éloigne : un seul
+        // incident. Les emprises font 0.75 secteur de long.
+        let offsets = [2.0, 0.85, 0.5, 0.85, 0.7, 2.0, 0.85, 2.0];
+        for (tick, offset) in offsets.iter().enumerate() {
+            let cars = [car_at(1, 0.0), car_at(0, offset * width)];
+            tracker.update(&cars, tick as u64, &config, &mut stats);
         }
-        assert_eq!(stats.collisions(), n);
+
+        assert_eq!(stats.collisions(), 1);
+        assert_eq!(stats.close_calls(), 1);
+        let collision = &stats.incidents()[0];
+        assert_eq!(collision.kind, IncidentKind::Collision);
+        assert_eq!(collision.cars, (0, 1));
+        assert_eq!((collision.start_tick, collision.end_tick), (1, Some(5)));
+        assert_eq!(collision.min_gap_tick, 2);
+        assert_eq!((collision.positions[0].0, collision.positions[1].0), (0.5 * width, 0.0));
+        assert_eq!(stats.incidents()[1].end_tick, Some(7));
     }
 }